@@ -0,0 +1,89 @@
+//! 命令行解析
+//! 不引入第三方库（如 clap），直接手动解析 `std::env::args()`
+
+/// 命令行帮助信息
+pub const USAGE: &str = "\
+用法: hello_world [命令]
+
+命令:
+    (无)              打印 Hello, world! 并运行 run_debug
+    list              列出所有章节
+    run <章节>        运行指定章节 如 `run course1` `run project_structure`
+    run --all         按顺序运行所有章节
+    bench             对比静态分发和动态分发的调用开销
+    --update-snapshots 重新生成所有章节的输出快照（snapshots 目录）
+    help              打印本帮助信息";
+
+/// 解析后的命令
+#[derive(Debug, PartialEq)]
+pub enum Command {
+    /// 不带参数时保持原有行为 方便在 VSCode 里调试
+    Debug,
+    Help,
+    List,
+    Run(String),
+    RunAll,
+    Bench,
+    UpdateSnapshots,
+}
+
+impl Command {
+    /// 解析命令行参数（不包含程序名本身）
+    pub fn parse<I>(args: I) -> Result<Command, String>
+    where
+        I: IntoIterator<Item = String>,
+    {
+        let mut args = args.into_iter();
+        let command = match args.next().as_deref() {
+            None => Command::Debug,
+            Some("help" | "-h" | "--help") => Command::Help,
+            Some("list") => Command::List,
+            Some("bench") => Command::Bench,
+            Some("--update-snapshots") => Command::UpdateSnapshots,
+            Some("run") => match args.next() {
+                None => return Err("run 需要指定章节或 --all".to_string()),
+                Some(arg) if arg == "--all" => Command::RunAll,
+                Some(arg) if arg.starts_with('-') => return Err(format!("未知参数: {arg}")),
+                Some(id) => Command::Run(id),
+            },
+            Some(other) => return Err(format!("未知命令: {other}")),
+        };
+
+        match args.next() {
+            None => Ok(command),
+            Some(extra) => Err(format!("多余的参数: {extra}")),
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn parse(args: &[&str]) -> Result<Command, String> {
+        Command::parse(args.iter().map(|arg| arg.to_string()))
+    }
+
+    #[test]
+    fn parse_commands() {
+        assert_eq!(parse(&[]), Ok(Command::Debug));
+        assert_eq!(parse(&["help"]), Ok(Command::Help));
+        assert_eq!(parse(&["list"]), Ok(Command::List));
+        assert_eq!(parse(&["run", "--all"]), Ok(Command::RunAll));
+        assert_eq!(parse(&["bench"]), Ok(Command::Bench));
+        assert_eq!(parse(&["--update-snapshots"]), Ok(Command::UpdateSnapshots));
+        assert_eq!(
+            parse(&["run", "project_structure"]),
+            Ok(Command::Run("project_structure".into()))
+        );
+    }
+
+    #[test]
+    fn parse_errors() {
+        assert!(parse(&["run"]).is_err());
+        assert!(parse(&["run", "--fast"]).is_err());
+        assert!(parse(&["list", "course1"]).is_err());
+        assert!(parse(&["bench", "--release"]).is_err());
+        assert!(parse(&["jump"]).is_err());
+    }
+}
@@ -12,6 +12,8 @@
 
 #![allow(dead_code)] // 防止提示 fields xxx are never read
 
+use std::io::{self, Write};
+
 /// # 基础语法 变量 语句 注释
 ///
 /// ## 代码注释
@@ -57,36 +59,37 @@
 ///  */
 /// }
 /// ```
-pub fn test_all() {
+pub fn test_all(out: &mut dyn Write) -> io::Result<()> {
     // 行注释 回车后不会自动添加下一个行注释 适用于单行注释
     /*
     块注释 打出注释开头-回车-注释内容-最后再加个回车 很方便
      */
-    println!("\n\ncourse 1:");
-    println!("========== test_format_print");
-    test_format_print();
-    println!("========== test_shadowing");
-    test_shadowing();
-    println!("========== test_tuple");
-    test_tuple();
-    println!("========== test_array_slice");
-    test_array_slice();
-    println!("========== test_struct");
-    test_struct();
-    println!("========== test_enum");
-    test_enum();
-    println!("========== test_func");
-    test_func();
-    println!("========== test_if");
-    test_if();
-    println!("========== test_while_for_loop");
-    test_while_for_loop();
+    writeln!(out, "\n\ncourse 1:")?;
+    writeln!(out, "========== test_format_print")?;
+    test_format_print(out)?;
+    writeln!(out, "========== test_shadowing")?;
+    test_shadowing(out)?;
+    writeln!(out, "========== test_tuple")?;
+    test_tuple(out)?;
+    writeln!(out, "========== test_array_slice")?;
+    test_array_slice(out)?;
+    writeln!(out, "========== test_struct")?;
+    test_struct(out)?;
+    writeln!(out, "========== test_enum")?;
+    test_enum(out)?;
+    writeln!(out, "========== test_func")?;
+    test_func(out)?;
+    writeln!(out, "========== test_if")?;
+    test_if(out)?;
+    writeln!(out, "========== test_while_for_loop")?;
+    test_while_for_loop(out)?;
+    Ok(())
 }
 
 /// 格式化输出
 /// - 实现了 `fmt::Display` 特征（trait）后该结构体可以被打印
 /// - 对于泛型容器（generic container）（如 `Vec<T>`）没实现该特征 需要使用 `fmt::Debug`
-fn test_format_print() {
+fn test_format_print(out: &mut dyn Write) -> io::Result<()> {
     let ss = format!(
         "a is {0}, b is {b_name}, num={num:>0width$}\n",
         "aaa",
@@ -94,9 +97,11 @@ fn test_format_print() {
         num = 3,
         width = 5
     );
-    print!("format print: {ss}");
+    write!(out, "format print: {ss}")?;
 
-    println!("stdout: normal print"); // io::stdout
+    // println! 直接写入 io::stdout 这里写入可注入的 out（运行器先写入内存缓冲 再转发到 stdout）
+    // 等价于 `println!("stdout: normal print")`
+    writeln!(out, "stdout: normal print")?;
     eprintln!("stderr: error print"); // io::stderr
 
     #[derive(Debug)]
@@ -106,75 +111,80 @@ fn test_format_print() {
         id: i32,
         name: String,
     }
-    println!(
+    writeln!(
+        out,
         "print by add 'derive(Debug)': {:?} {1:?}\nprint beauty: {1:#?}",
         Structure1(3),
         Structure2 {
             id: 1,
             name: String::from("aa")
         }
-    );
+    )?;
+    Ok(())
 }
 
 /// # 文档注释
 /// 探究 常量 不可变变量 可变变量 变量遮蔽（ variable shadowing ）（也有翻译重影机制）
-fn test_shadowing() {
+fn test_shadowing(out: &mut dyn Write) -> io::Result<()> {
     // 不可变变量可以多次定义 - 遮蔽Shadowing 地址会改变
 
     let a = 1;
-    println!("Hello, world! tmp is {}.", a);
+    writeln!(out, "Hello, world! tmp is {}.", a)?;
     let addr = &a as *const i32 as usize;
-    println!("addr: 0x{:X}", addr);
+    writeln!(out, "addr: 0x{:X}", addr)?;
 
     let a = 2;
-    println!("Hello, world! tmp is {}.", a);
+    writeln!(out, "Hello, world! tmp is {}.", a)?;
     let addr = &a as *const i32 as usize;
-    println!("addr: 0x{:X}  => address changed after shadowing", addr);
+    writeln!(out, "addr: 0x{:X}  => address changed after shadowing", addr)?;
 
     // 这是可变变量 允许直接修改
     // 个人理解：只有重新赋值的情况才需要mut 然后他具有传染性 只要struct里的任何一个属性需要修改 整个struct就需要是mut的
 
     let mut a = 2;
-    println!("Hello, world! tmp is {}.", a);
+    writeln!(out, "Hello, world! tmp is {}.", a)?;
     let addr = &a as *const i32 as usize;
-    println!("addr: 0x{:X}", addr);
+    writeln!(out, "addr: 0x{:X}", addr)?;
 
     a = 2;
-    println!("Hello, world! tmp is {}.", a);
+    writeln!(out, "Hello, world! tmp is {}.", a)?;
     let addr = &a as *const i32 as usize;
-    println!(
+    writeln!(
+        out,
         "addr: 0x{:X}  => address not changed when mutable variable",
         addr
-    );
+    )?;
 
     // 而常量 不能重新定义
     const B_TMP: i64 = 0;
     // const B_TMP: i64 = 1; // 会报错
-    println!("Hello, world! const is {}.", B_TMP);
+    writeln!(out, "Hello, world! const is {}.", B_TMP)?;
     // 另一种常量 用 static 声明。具有 'static 生命周期的，可以是可变的变量（译注：须使用 static mut 关键字）
+    Ok(())
 }
 
 /// 元组简单使用
-fn test_tuple() {
+fn test_tuple(out: &mut dyn Write) -> io::Result<()> {
     // 元组 tuple
     let tup = (1, true, 6.1);
     // 解构（ deconstruct ）
     let (x, y, z) = tup;
-    println!("tuple deconstruct {}:{}:{}", x, y, z);
+    writeln!(out, "tuple deconstruct {}:{}:{}", x, y, z)?;
+    Ok(())
 }
 
 /// 数组和切片
-fn test_array_slice() {
+fn test_array_slice(out: &mut dyn Write) -> io::Result<()> {
     // 定长数组 array
     let xs = [1, 2, 3, 4, 5];
     // 所有元素可以初始化成相同的值
     let ys = [0; 9];
 
     // `len` 返回数组的大小
-    println!("array size: {}", xs.len());
+    writeln!(out, "array size: {}", xs.len())?;
 
     // 数组是在栈中分配的
-    println!("array occupies {} bytes", std::mem::size_of_val(&xs));
+    writeln!(out, "array occupies {} bytes", std::mem::size_of_val(&xs))?;
 
     /*
     数组（array）是一组拥有相同类型 T 的对象的集合，在内存中是连续存储的。
@@ -189,22 +199,23 @@ fn test_array_slice() {
     */
 
     // 此函数借用一个 slice
-    fn analyze_slice(slice: &[i32]) {
-        println!("first element of the slice: {}", slice[0]);
-        println!("the slice has {} elements", slice.len());
+    fn analyze_slice(out: &mut dyn Write, slice: &[i32]) -> io::Result<()> {
+        writeln!(out, "first element of the slice: {}", slice[0])?;
+        writeln!(out, "the slice has {} elements", slice.len())
     }
 
     // 数组可以自动被借用成为 slice
-    println!(">>> borrow the whole array as a slice <<<");
-    analyze_slice(&xs);
+    writeln!(out, ">>> borrow the whole array as a slice <<<")?;
+    analyze_slice(out, &xs)?;
 
     // slice 可以指向数组的一部分
-    println!(">>> borrow a section of the array as a slice <<<");
-    analyze_slice(&ys[1..4]);
+    writeln!(out, ">>> borrow a section of the array as a slice <<<")?;
+    analyze_slice(out, &ys[1..4])?;
+    Ok(())
 }
 
 /// 结构体
-fn test_struct() {
+fn test_struct(out: &mut dyn Write) -> io::Result<()> {
     // 单元结构体 据说在泛型中很有用
     struct Unit;
 
@@ -213,12 +224,12 @@ fn test_struct() {
     // 实例化一个元组结构体
     let pair = Pair(1, 0.1);
     // 访问元组结构体的字段
-    println!("tuple_struct: pair contains {:?} and {:?}", pair.0, pair.1);
+    writeln!(out, "tuple_struct: pair contains {:?} and {:?}", pair.0, pair.1)?;
     // 解构一个元组结构体
     let Pair(integer, decimal) = pair;
-    println!("deconstruct: pair contains {:?} and {:?}", integer, decimal);
+    writeln!(out, "deconstruct: pair contains {:?} and {:?}", integer, decimal)?;
 
-    // 经典的 C 语言风格结构体（C struct）
+    // 经典的 C 语言风格结构体（C struct） 扩展为 f64 向量并重载运算符见 course10::Vec2
     struct Point {
         x: f32,
         y: f32,
@@ -228,24 +239,26 @@ fn test_struct() {
     let y = 0.4f32;
     let point: Point = Point { x, y };
     // 访问 point 的字段
-    println!("struct: point coordinates: ({}, {})", point.x, point.y);
+    writeln!(out, "struct: point coordinates: ({}, {})", point.x, point.y)?;
     // 使用结构体更新语法创建新的 point，这样可以用到之前的 point 的字段
     let bottom_right = Point { x: 5.2, ..point };
     // `bottom_right.y` 与 `point.y` 一样，因为这个字段就是从 `point` 中来的
-    println!(
+    writeln!(
+        out,
         "struct: second point: ({}, {})",
         bottom_right.x, bottom_right.y
-    );
+    )?;
     // 使用 `let` 绑定来解构 point
     let Point {
         x: left_edge,
         y: top_edge,
     } = point;
-    println!("deconstruct: second point: ({}, {})", left_edge, top_edge);
+    writeln!(out, "deconstruct: second point: ({}, {})", left_edge, top_edge)?;
+    Ok(())
 }
 
 /// 枚举
-fn test_enum() {
+fn test_enum(out: &mut dyn Write) -> io::Result<()> {
     // 枚举类型的取值独立 也可以给予赋值
     enum Event {
         A,
@@ -254,26 +267,27 @@ fn test_enum() {
         D { x: i64, y: i64 },
     }
 
-    fn inspect(event: Event) {
+    fn inspect(out: &mut dyn Write, event: Event) -> io::Result<()> {
         match event {
-            Event::A => println!("the a"),
-            Event::B(c) => println!("the char is {c}"),
-            Event::C(s) => println!("the string is {s}"),
-            Event::D { x, y } => println!("the x,y is {x},{y}"),
+            Event::A => writeln!(out, "the a"),
+            Event::B(c) => writeln!(out, "the char is {c}"),
+            Event::C(s) => writeln!(out, "the string is {s}"),
+            Event::D { x, y } => writeln!(out, "the x,y is {x},{y}"),
         }
     }
 
     // 别名 主要用途是避免写出冗长的模板化代码（boilerplate code）。如 IoResult<T> 是 Result<T, IoError> 类型的别名。
     type E = Event;
 
-    inspect(E::A);
-    inspect(E::B('m'));
-    inspect(E::C(String::from("ssss")));
-    inspect(E::D { x: 2, y: 3 });
+    inspect(out, E::A)?;
+    inspect(out, E::B('m'))?;
+    inspect(out, E::C(String::from("ssss")))?;
+    inspect(out, E::D { x: 2, y: 3 })?;
+    Ok(())
 }
 
 /// 函数体表达式 函数体 lambda
-fn test_func() {
+fn test_func(out: &mut dyn Write) -> io::Result<()> {
     // 函数体表达式 不可用return
     let x = 5;
 
@@ -282,49 +296,53 @@ fn test_func() {
         x + 1
     };
 
-    println!("x is {}", x);
-    println!("y is {}", y);
+    writeln!(out, "x is {}", x)?;
+    writeln!(out, "y is {}", y)?;
 
     // 函数 返回类型必须明确 不可重复定义
 
     fn five() -> i32 {
         5
     }
-    println!("five() 's value is {}", five());
+    writeln!(out, "five() 's value is {}", five())?;
 
+    #[allow(clippy::needless_return)] // 演示 return 写法
     fn six() -> i32 {
         return 6;
     }
-    println!("six() 's value is {}", six());
+    writeln!(out, "six() 's value is {}", six())?;
 
     // lambda
 
     let seven = || 7;
-    println!("seven() 's value is {}", seven());
+    writeln!(out, "seven() 's value is {}", seven())?;
     let add = |a, b| a + b;
-    println!("add(six(), seven()) 's value is {}", add(six(), seven()));
+    writeln!(out, "add(six(), seven()) 's value is {}", add(six(), seven()))?;
+    Ok(())
 }
 
 /// 条件语句
-fn test_if() {
+fn test_if(out: &mut dyn Write) -> io::Result<()> {
     // if条件可以加括号也可以不加 if语句能返回值
     let a = 1;
+    #[allow(clippy::needless_bool)] // 演示 if 返回值
     let b = if a == 0 { false } else { true };
-    println!("a is {}, b is {}", a, b);
+    writeln!(out, "a is {}, b is {}", a, b)?;
+    Ok(())
 }
 
 /// 循环语句
-fn test_while_for_loop() {
+fn test_while_for_loop(out: &mut dyn Write) -> io::Result<()> {
     // 目前没有 do-while 和 fori
-    println!(">>> while i < 3 <<<");
+    writeln!(out, ">>> while i < 3 <<<")?;
     let mut i = 0;
     while i < 3 {
-        println!("i is {}", i);
+        writeln!(out, "i is {}", i)?;
         i += 1;
     }
 
     // 自带的 while-true 及 loop  这里用了返回值的方式
-    println!(">>> loop and break if 'S' <<<");
+    writeln!(out, ">>> loop and break if 'S' <<<")?;
     let s = ['R', 'U', 'S', 'T'];
     let mut i = 0;
     let index = loop {
@@ -334,16 +352,17 @@ fn test_while_for_loop() {
         }
         i += 1;
     };
-    println!("index of 'S' is {}", index);
+    writeln!(out, "index of 'S' is {}", index)?;
 
     // for 用于遍历迭代
-    println!("for in iter");
+    writeln!(out, "for in iter")?;
     let a = [0, 1, 2];
     for i in a.iter() {
-        println!("i is {}", i);
+        writeln!(out, "i is {}", i)?;
     }
-    println!("for in 0..5");
+    writeln!(out, "for in 0..5")?;
     for i in 0..5 {
-        println!("i is {}", i);
+        writeln!(out, "i is {}", i)?;
     }
+    Ok(())
 }
@@ -0,0 +1,675 @@
+//! 运算符重载与数值特征
+//! 把 course1 中只用来打印的 `Point { x: f32, y: f32 }` 扩展为可复用的二维 / 三维向量
+//!
+//! 分量类型从 f32 改成了 f64：
+//! - f64 是 Rust 浮点字面量的默认类型 写 `Vec2 { x: 1.0, y: 2.0 }` 不需要 `f32` 后缀
+//! - 旋转要经过 sin_cos 长度要经过 sqrt f32 只有约 7 位有效数字 误差累积很快
+//! - 测试中的性质检查以 1e-9 的相对误差比较 f32 的精度（约 1.2e-7）达不到
+//!
+//! 本章学习：
+//! - std::ops：Add Sub Mul Div Neg 以及对应的 *Assign 版本 Index IndexMut
+//! - Display（支持 `{:.2}` 精度）与 FromStr
+//! - PartialEq PartialOrd 的派生 以及浮点数只能做到 "Partial" 的原因
+//! - Sum：让 `iter.sum()` 直接得到向量
+//! - 用宏为多个类型生成相同的实现
+//! - 几何运算：点积 叉积 旋转 距离
+
+use std::error::Error;
+use std::fmt;
+use std::io::{self, Write};
+use std::iter::Sum;
+use std::ops::{Add, AddAssign, Div, Index, IndexMut, Mul, Neg, Sub, SubAssign};
+use std::str::FromStr;
+
+/// 二维向量（点）
+///
+/// 派生的 PartialOrd 按字段顺序比较（先比较 x 再比较 y）
+/// 因为 f64 存在 NaN 所以只能实现 PartialOrd 而不是 Ord
+#[derive(Debug, Clone, Copy, Default, PartialEq, PartialOrd)]
+pub struct Vec2 {
+    pub x: f64,
+    pub y: f64,
+}
+
+/// 三维向量
+#[derive(Debug, Clone, Copy, Default, PartialEq, PartialOrd)]
+pub struct Vec3 {
+    pub x: f64,
+    pub y: f64,
+    pub z: f64,
+}
+
+/// 解析向量失败
+#[derive(Debug, PartialEq)]
+pub enum ParseVectorError {
+    /// 分量个数不对
+    Dimension {
+        expected: usize,
+        found: usize,
+    },
+    InvalidNumber(String),
+}
+
+impl fmt::Display for ParseVectorError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ParseVectorError::Dimension { expected, found } => {
+                write!(f, "expected {expected} components, found {found}")
+            }
+            ParseVectorError::InvalidNumber(number) => write!(f, "invalid number {number:?}"),
+        }
+    }
+}
+
+impl Error for ParseVectorError {}
+
+/// 为向量类型生成逐分量的运算符 以及不依赖维度的方法
+macro_rules! impl_vector {
+    ($name:ident { $($field:ident),+ }) => {
+        impl $name {
+            /// 分量个数
+            pub const DIM: usize = [$(stringify!($field)),+].len();
+            pub const ZERO: $name = $name { $($field: 0.0),+ };
+
+            pub const fn new($($field: f64),+) -> Self {
+                $name { $($field),+ }
+            }
+
+            pub fn dot(self, other: Self) -> f64 {
+                0.0 $(+ self.$field * other.$field)+
+            }
+
+            pub fn length(self) -> f64 {
+                self.dot(self).sqrt()
+            }
+
+            pub fn distance(self, other: Self) -> f64 {
+                (self - other).length()
+            }
+
+            /// 单位向量 零向量没有方向 返回 None
+            pub fn normalize(self) -> Option<Self> {
+                let length = self.length();
+                (length > 0.0).then(|| self / length)
+            }
+
+            /// 两个向量的夹角（弧度） 任意一个是零向量时返回 None
+            pub fn angle(self, other: Self) -> Option<f64> {
+                let (a, b) = (self.normalize()?, other.normalize()?);
+                // 浮点误差可能让点积略微超出 [-1, 1]
+                Some(a.dot(b).clamp(-1.0, 1.0).acos())
+            }
+
+            /// 线性插值 t = 0 时为 self t = 1 时为 other
+            pub fn lerp(self, other: Self, t: f64) -> Self {
+                self + (other - self) * t
+            }
+        }
+
+        impl Add for $name {
+            type Output = Self;
+
+            fn add(self, other: Self) -> Self {
+                $name { $($field: self.$field + other.$field),+ }
+            }
+        }
+
+        impl Sub for $name {
+            type Output = Self;
+
+            fn sub(self, other: Self) -> Self {
+                $name { $($field: self.$field - other.$field),+ }
+            }
+        }
+
+        impl AddAssign for $name {
+            fn add_assign(&mut self, other: Self) {
+                $(self.$field += other.$field;)+
+            }
+        }
+
+        impl SubAssign for $name {
+            fn sub_assign(&mut self, other: Self) {
+                $(self.$field -= other.$field;)+
+            }
+        }
+
+        impl Neg for $name {
+            type Output = Self;
+
+            fn neg(self) -> Self {
+                $name { $($field: -self.$field),+ }
+            }
+        }
+
+        /// 数乘 `v * 2.0`
+        impl Mul<f64> for $name {
+            type Output = Self;
+
+            fn mul(self, scalar: f64) -> Self {
+                $name { $($field: self.$field * scalar),+ }
+            }
+        }
+
+        /// 运算符左边是 f64 时需要为 f64 实现 `2.0 * v`
+        impl Mul<$name> for f64 {
+            type Output = $name;
+
+            fn mul(self, vector: $name) -> $name {
+                vector * self
+            }
+        }
+
+        impl Div<f64> for $name {
+            type Output = Self;
+
+            fn div(self, scalar: f64) -> Self {
+                $name { $($field: self.$field / scalar),+ }
+            }
+        }
+
+        /// 按下标访问分量 `v[0]` 越界时 panic
+        impl Index<usize> for $name {
+            type Output = f64;
+
+            fn index(&self, index: usize) -> &f64 {
+                match [$(&self.$field),+].into_iter().nth(index) {
+                    Some(component) => component,
+                    None => panic!(
+                        "index out of bounds: {} has {} components but the index is {}",
+                        stringify!($name),
+                        Self::DIM,
+                        index
+                    ),
+                }
+            }
+        }
+
+        impl IndexMut<usize> for $name {
+            fn index_mut(&mut self, index: usize) -> &mut f64 {
+                match [$(&mut self.$field),+].into_iter().nth(index) {
+                    Some(component) => component,
+                    None => panic!(
+                        "index out of bounds: {} has {} components but the index is {}",
+                        stringify!($name),
+                        Self::DIM,
+                        index
+                    ),
+                }
+            }
+        }
+
+        impl Sum for $name {
+            fn sum<I: Iterator<Item = Self>>(iter: I) -> Self {
+                iter.fold(Self::ZERO, Add::add)
+            }
+        }
+
+        /// 让 `vectors.iter().sum()` 也能使用
+        impl<'a> Sum<&'a $name> for $name {
+            fn sum<I: Iterator<Item = &'a Self>>(iter: I) -> Self {
+                iter.copied().sum()
+            }
+        }
+
+        /// 输出 `(1, 2)` 指定精度时每个分量都使用该精度 `{:.2}` 输出 `(1.00, 2.00)`
+        impl fmt::Display for $name {
+            fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+                let components = [$(self.$field),+];
+                write!(f, "(")?;
+                for (i, component) in components.iter().enumerate() {
+                    if i > 0 {
+                        write!(f, ", ")?;
+                    }
+                    match f.precision() {
+                        Some(precision) => write!(f, "{component:.precision$}")?,
+                        None => write!(f, "{component}")?,
+                    }
+                }
+                write!(f, ")")
+            }
+        }
+
+        /// 解析 `(1, 2)` 或 `1,2` 与 Display 的输出互逆
+        impl FromStr for $name {
+            type Err = ParseVectorError;
+
+            fn from_str(s: &str) -> Result<Self, Self::Err> {
+                let s = s.trim();
+                let s = s
+                    .strip_prefix('(')
+                    .and_then(|s| s.strip_suffix(')'))
+                    .unwrap_or(s);
+                let components = s
+                    .split(',')
+                    .map(|part| {
+                        part.trim()
+                            .parse::<f64>()
+                            .map_err(|_| ParseVectorError::InvalidNumber(part.trim().to_string()))
+                    })
+                    .collect::<Result<Vec<_>, _>>()?;
+                if components.len() != Self::DIM {
+                    return Err(ParseVectorError::Dimension {
+                        expected: Self::DIM,
+                        found: components.len(),
+                    });
+                }
+                let mut components = components.into_iter();
+                // 长度已经检查过 这里的 unwrap 不会失败
+                Ok($name { $($field: components.next().unwrap()),+ })
+            }
+        }
+    };
+}
+
+impl_vector!(Vec2 { x, y });
+impl_vector!(Vec3 { x, y, z });
+
+impl Vec2 {
+    /// 二维叉积 即三维叉积的 z 分量 正数表示 other 在 self 的逆时针方向
+    pub fn cross(self, other: Self) -> f64 {
+        self.x * other.y - self.y * other.x
+    }
+
+    /// 绕原点逆时针旋转（弧度）
+    pub fn rotate(self, radians: f64) -> Self {
+        let (sin, cos) = radians.sin_cos();
+        Vec2 {
+            x: self.x * cos - self.y * sin,
+            y: self.x * sin + self.y * cos,
+        }
+    }
+
+    /// 绕 center 逆时针旋转
+    pub fn rotate_around(self, center: Self, radians: f64) -> Self {
+        (self - center).rotate(radians) + center
+    }
+
+    /// 逆时针垂直的向量
+    pub fn perp(self) -> Self {
+        Vec2 {
+            x: -self.y,
+            y: self.x,
+        }
+    }
+}
+
+impl Vec3 {
+    pub fn cross(self, other: Self) -> Self {
+        Vec3 {
+            x: self.y * other.z - self.z * other.y,
+            y: self.z * other.x - self.x * other.z,
+            z: self.x * other.y - self.y * other.x,
+        }
+    }
+
+    /// 绕 axis 按右手定则旋转（罗德里格旋转公式） axis 是零向量时原样返回
+    pub fn rotate(self, axis: Self, radians: f64) -> Self {
+        let Some(k) = axis.normalize() else {
+            return self;
+        };
+        let (sin, cos) = radians.sin_cos();
+        self * cos + k.cross(self) * sin + k * (k.dot(self) * (1.0 - cos))
+    }
+}
+
+impl From<(f64, f64)> for Vec2 {
+    fn from((x, y): (f64, f64)) -> Self {
+        Vec2 { x, y }
+    }
+}
+
+impl From<(f64, f64, f64)> for Vec3 {
+    fn from((x, y, z): (f64, f64, f64)) -> Self {
+        Vec3 { x, y, z }
+    }
+}
+
+/// 放到 z = 0 的平面上
+impl From<Vec2> for Vec3 {
+    fn from(v: Vec2) -> Self {
+        Vec3 {
+            x: v.x,
+            y: v.y,
+            z: 0.0,
+        }
+    }
+}
+
+/// 多边形面积（鞋带公式） 顶点按逆时针排列时为正
+pub fn polygon_area(vertices: &[Vec2]) -> f64 {
+    let edges = vertices.iter().zip(vertices.iter().cycle().skip(1));
+    edges.map(|(a, b)| a.cross(*b)).sum::<f64>() / 2.0
+}
+
+/// 重心 没有顶点时返回 None
+pub fn centroid(points: &[Vec2]) -> Option<Vec2> {
+    (!points.is_empty()).then(|| points.iter().sum::<Vec2>() / points.len() as f64)
+}
+
+/// 运算符重载的简单演示
+pub fn test_all(out: &mut dyn Write) -> io::Result<()> {
+    writeln!(out, "\n\ncourse 10:")?;
+    let a = Vec2::new(3.0, 4.0);
+    let b: Vec2 = "(1, -2)".parse().unwrap();
+    writeln!(out, "a = {a}, b = {b}")?;
+    writeln!(
+        out,
+        "a + b = {}, a - b = {}, -a = {}, 2 * a = {}",
+        a + b,
+        a - b,
+        -a,
+        2.0 * a
+    )?;
+    writeln!(
+        out,
+        "|a| = {}, a·b = {}, a×b = {}, distance = {:.3}",
+        a.length(),
+        a.dot(b),
+        a.cross(b),
+        a.distance(b)
+    )?;
+    writeln!(
+        out,
+        "a rotated 90°: {:.3}, a[0] = {}, a < b: {}",
+        a.rotate(std::f64::consts::FRAC_PI_2),
+        a[0],
+        a < b
+    )?;
+
+    let square = [
+        Vec2::new(0.0, 0.0),
+        Vec2::new(2.0, 0.0),
+        Vec2::new(2.0, 2.0),
+        Vec2::new(0.0, 2.0),
+    ];
+    writeln!(
+        out,
+        "square: sum = {}, centroid = {:?}, area = {}",
+        square.iter().sum::<Vec2>(),
+        centroid(&square).map(|c| c.to_string()),
+        polygon_area(&square)
+    )?;
+
+    let x = Vec3::new(1.0, 0.0, 0.0);
+    let y = Vec3::new(0.0, 1.0, 0.0);
+    writeln!(
+        out,
+        "x × y = {}, x rotated around z by 90°: {:.3}",
+        x.cross(y),
+        x.rotate(x.cross(y), std::f64::consts::FRAC_PI_2)
+    )?;
+    writeln!(
+        out,
+        "parse errors: {:?} {:?}",
+        "1,2,3".parse::<Vec2>(),
+        "1,a,3".parse::<Vec3>()
+    )?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use std::f64::consts::{FRAC_PI_2, PI};
+
+    const EPSILON: f64 = 1e-9;
+
+    fn close(a: f64, b: f64) -> bool {
+        (a - b).abs() <= EPSILON * (1.0 + a.abs().max(b.abs()))
+    }
+
+    fn close2(a: Vec2, b: Vec2) -> bool {
+        close(a.x, b.x) && close(a.y, b.y)
+    }
+
+    fn close3(a: Vec3, b: Vec3) -> bool {
+        close(a.x, b.x) && close(a.y, b.y) && close(a.z, b.z)
+    }
+
+    /// 线性同余生成器 固定种子保证测试可重复
+    struct Lcg(u64);
+
+    impl Lcg {
+        fn next_f64(&mut self) -> f64 {
+            self.0 = self
+                .0
+                .wrapping_mul(6364136223846793005)
+                .wrapping_add(1442695040888963407);
+            // 取高 53 位映射到 [-100, 100)
+            ((self.0 >> 11) as f64 / (1u64 << 53) as f64) * 200.0 - 100.0
+        }
+
+        fn vec2(&mut self) -> Vec2 {
+            Vec2::new(self.next_f64(), self.next_f64())
+        }
+
+        fn vec3(&mut self) -> Vec3 {
+            Vec3::new(self.next_f64(), self.next_f64(), self.next_f64())
+        }
+    }
+
+    /// 对随机生成的输入检查性质
+    fn for_all<T>(mut generate: impl FnMut(&mut Lcg) -> T, check: impl Fn(T)) {
+        let mut rng = Lcg(0x5eed);
+        for _ in 0..500 {
+            check(generate(&mut rng));
+        }
+    }
+
+    #[test]
+    fn operators() {
+        let a = Vec2::new(1.0, 2.0);
+        let b = Vec2::new(3.0, -1.0);
+        assert_eq!(a + b, Vec2::new(4.0, 1.0));
+        assert_eq!(a - b, Vec2::new(-2.0, 3.0));
+        assert_eq!(-a, Vec2::new(-1.0, -2.0));
+        assert_eq!(a * 3.0, 3.0 * a);
+        assert_eq!(b / 2.0, Vec2::new(1.5, -0.5));
+
+        let mut c = a;
+        c += b;
+        c -= a;
+        assert_eq!(c, b);
+
+        let mut v = Vec3::new(1.0, 2.0, 3.0);
+        assert_eq!((v[0], v[1], v[2]), (1.0, 2.0, 3.0));
+        v[2] = 9.0;
+        assert_eq!(v.z, 9.0);
+        assert_eq!(Vec3::from(a), Vec3::new(1.0, 2.0, 0.0));
+        assert_eq!(Vec2::from((1.0, 2.0)), a);
+    }
+
+    #[test]
+    #[should_panic(expected = "Vec2 has 2 components but the index is 2")]
+    fn index_out_of_bounds() {
+        let _ = Vec2::ZERO[2];
+    }
+
+    #[test]
+    fn ordering() {
+        // 先比较 x 再比较 y
+        assert!(Vec2::new(1.0, 5.0) < Vec2::new(2.0, 0.0));
+        assert!(Vec2::new(1.0, 0.0) < Vec2::new(1.0, 1.0));
+        // NaN 与任何值都无法比较
+        let nan = Vec2::new(f64::NAN, 0.0);
+        assert_eq!(nan.partial_cmp(&Vec2::ZERO), None);
+        assert_ne!(nan, nan);
+
+        let mut points = vec![
+            Vec2::new(2.0, 1.0),
+            Vec2::new(0.0, 3.0),
+            Vec2::new(2.0, 0.0),
+        ];
+        points.sort_by(|a, b| a.partial_cmp(b).unwrap());
+        assert_eq!(
+            points,
+            [
+                Vec2::new(0.0, 3.0),
+                Vec2::new(2.0, 0.0),
+                Vec2::new(2.0, 1.0)
+            ]
+        );
+    }
+
+    #[test]
+    fn display_and_parse() {
+        assert_eq!(Vec2::new(1.0, -2.5).to_string(), "(1, -2.5)");
+        assert_eq!(
+            format!("{:.2}", Vec3::new(1.0, 2.0, 1.0 / 3.0)),
+            "(1.00, 2.00, 0.33)"
+        );
+
+        assert_eq!("(1, 2)".parse(), Ok(Vec2::new(1.0, 2.0)));
+        assert_eq!(" 1.5,-2 ,3 ".parse(), Ok(Vec3::new(1.5, -2.0, 3.0)));
+        assert_eq!(
+            "(1, 2, 3)".parse::<Vec2>(),
+            Err(ParseVectorError::Dimension {
+                expected: 2,
+                found: 3
+            })
+        );
+        assert_eq!(
+            "(1, x)".parse::<Vec2>(),
+            Err(ParseVectorError::InvalidNumber("x".into()))
+        );
+        assert_eq!(
+            "".parse::<Vec2>(),
+            Err(ParseVectorError::InvalidNumber(String::new()))
+        );
+    }
+
+    #[test]
+    fn sum() {
+        let points = [
+            Vec2::new(1.0, 1.0),
+            Vec2::new(2.0, 3.0),
+            Vec2::new(-3.0, 0.0),
+        ];
+        assert_eq!(points.iter().sum::<Vec2>(), Vec2::new(0.0, 4.0));
+        assert_eq!(points.into_iter().sum::<Vec2>(), Vec2::new(0.0, 4.0));
+        assert_eq!(std::iter::empty::<Vec3>().sum::<Vec3>(), Vec3::ZERO);
+        assert_eq!(centroid(&points[..2]), Some(Vec2::new(1.5, 2.0)));
+        assert_eq!(centroid(&[]), None);
+    }
+
+    #[test]
+    fn geometry() {
+        let a = Vec2::new(3.0, 4.0);
+        assert_eq!(a.length(), 5.0);
+        assert_eq!(a.distance(Vec2::ZERO), 5.0);
+        assert_eq!(a.normalize(), Some(Vec2::new(0.6, 0.8)));
+        assert_eq!(Vec2::ZERO.normalize(), None);
+        assert!(close2(
+            Vec2::new(1.0, 0.0).rotate(FRAC_PI_2),
+            Vec2::new(0.0, 1.0)
+        ));
+        assert!(close2(
+            Vec2::new(2.0, 1.0).rotate_around(Vec2::new(1.0, 1.0), PI),
+            Vec2::new(0.0, 1.0)
+        ));
+        assert!(close(
+            Vec2::new(1.0, 0.0).angle(Vec2::new(0.0, 2.0)).unwrap(),
+            FRAC_PI_2
+        ));
+        assert_eq!(a.lerp(Vec2::ZERO, 0.5), Vec2::new(1.5, 2.0));
+
+        let x = Vec3::new(1.0, 0.0, 0.0);
+        let y = Vec3::new(0.0, 1.0, 0.0);
+        let z = Vec3::new(0.0, 0.0, 1.0);
+        assert_eq!(x.cross(y), z);
+        assert_eq!(y.cross(x), -z);
+        assert!(close3(x.rotate(z, FRAC_PI_2), y));
+        assert_eq!(x.rotate(Vec3::ZERO, 1.0), x);
+
+        // 逆时针为正 顺时针为负
+        let triangle = [Vec2::ZERO, Vec2::new(4.0, 0.0), Vec2::new(0.0, 3.0)];
+        assert_eq!(polygon_area(&triangle), 6.0);
+        let mut reversed = triangle;
+        reversed.reverse();
+        assert_eq!(polygon_area(&reversed), -6.0);
+    }
+
+    #[test]
+    fn algebraic_properties() {
+        for_all(
+            |rng| (rng.vec2(), rng.vec2(), rng.next_f64()),
+            |(a, b, k)| {
+                assert_eq!(a + b, b + a);
+                assert!(close2(a + b - b, a));
+                assert_eq!(a + -a, Vec2::ZERO);
+                assert!(close2(k * (a + b), k * a + k * b));
+                assert!(close(a.dot(b), b.dot(a)));
+                assert!(close(a.cross(b), -b.cross(a)));
+                assert!(close(a.perp().dot(a), 0.0));
+            },
+        );
+    }
+
+    #[test]
+    fn metric_properties() {
+        for_all(
+            |rng| (rng.vec2(), rng.vec2(), rng.vec2()),
+            |(a, b, c)| {
+                assert!(a.distance(b) >= 0.0);
+                assert!(close(a.distance(b), b.distance(a)));
+                assert!(a.distance(c) <= a.distance(b) + b.distance(c) + EPSILON);
+                // 柯西-施瓦茨不等式
+                assert!(a.dot(b).abs() <= a.length() * b.length() * (1.0 + EPSILON));
+            },
+        );
+    }
+
+    #[test]
+    fn rotation_properties() {
+        for_all(
+            |rng| (rng.vec2(), rng.vec2(), rng.next_f64()),
+            |(a, b, angle)| {
+                let (ra, rb) = (a.rotate(angle), b.rotate(angle));
+                // 旋转保持长度 距离和夹角 且可以逆转
+                assert!(close(ra.length(), a.length()));
+                assert!(close(ra.distance(rb), a.distance(b)));
+                assert!(close(ra.dot(rb), a.dot(b)));
+                assert!(close2(ra.rotate(-angle), a));
+            },
+        );
+        for_all(
+            |rng| (rng.vec3(), rng.vec3(), rng.next_f64()),
+            |(v, axis, angle)| {
+                let rotated = v.rotate(axis, angle);
+                assert!(close(rotated.length(), v.length()));
+                // 平行于轴的分量不变
+                assert!(close(rotated.dot(axis), v.dot(axis)));
+                assert!(close3(rotated.rotate(axis, -angle), v));
+            },
+        );
+    }
+
+    #[test]
+    fn cross_product_properties() {
+        for_all(
+            |rng| (rng.vec3(), rng.vec3()),
+            |(a, b)| {
+                let c = a.cross(b);
+                let scale = a.length() * b.length();
+                // 叉积垂直于两个输入
+                assert!(c.dot(a).abs() <= EPSILON * scale * a.length());
+                assert!(c.dot(b).abs() <= EPSILON * scale * b.length());
+                assert!(close3(c, -b.cross(a)));
+                // |a × b|² + (a·b)² = |a|²|b|²
+                assert!(close(c.dot(c) + a.dot(b).powi(2), scale * scale));
+            },
+        );
+    }
+
+    #[test]
+    fn display_parse_round_trip() {
+        for_all(
+            |rng| (rng.vec2(), rng.vec3()),
+            |(a, b)| {
+                assert_eq!(a.to_string().parse(), Ok(a));
+                assert_eq!(b.to_string().parse(), Ok(b));
+            },
+        );
+    }
+}
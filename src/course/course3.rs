@@ -5,6 +5,8 @@
 // 箱级allow属性 不带!为结构/模块/函数级别
 #![allow(dead_code)] // 防止提示 fields xxx are never read
 
+use std::io::{self, Write};
+
 struct Aser {
     name: String,
     age: i32,
@@ -16,6 +18,19 @@ impl Aser {
     }
 }
 
+/// 结构体方法的简单演示 更多用法见单元测试
+pub fn test_all(out: &mut dyn Write) -> io::Result<()> {
+    writeln!(out, "\n\ncourse 3:")?;
+    let a = Aser {
+        name: "aaa".to_string(),
+        age: 1,
+    };
+    writeln!(out, "get_tag: {}", a.get_tag())?;
+    let b = Aser { age: 2, ..a };
+    writeln!(out, "struct update syntax, get_tag: {}", b.get_tag())?;
+    Ok(())
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -1,6 +1,25 @@
 //! 本章学习：
 //! 给函数标注声明周期（编译器无法推导生命周期的情况下，手动标注）
 
+use std::io::{self, Write};
+
+/// 生命周期的简单演示 详细说明见单元测试
+pub fn test_all(out: &mut dyn Write) -> io::Result<()> {
+    writeln!(out, "\n\ncourse 4:")?;
+    fn longest<'a>(x: &'a str, y: &'a str) -> &'a str {
+        if x.len() > y.len() {
+            x
+        } else {
+            y
+        }
+    }
+
+    let string1 = String::from("abcd");
+    let string2 = String::from("xyz");
+    writeln!(out, "The longest string is {}", longest(&string1, &string2))?;
+    Ok(())
+}
+
 #[cfg(test)]
 mod test_lifetime {
 
@@ -1,30 +1,48 @@
 //! rust 实现简单链表
+//!
+//! 本章学习：
+//! - 用 Option<Box<_>> 实现单向链表（栈）
+//! - 泛型 以及 peek / peek_mut 返回引用
+//! - 三种迭代器 into_iter / iter / iter_mut
+//! - 常用特征 FromIterator Extend Debug Clone PartialEq
+//! - 手动实现 Drop 防止爆栈
+//! - 持久化链表：Rc / Arc 共享所有权（见 persistent）
+//! - 双端队列：Rc<RefCell<_>> 和裸指针两种实现（见 deque unsafe_deque）
 
 #![allow(dead_code)]
 
-use std::mem;
+use std::fmt;
+use std::io::{self, Write};
+
+#[cfg(test)]
+#[macro_use]
+mod deque_tests;
+
+pub mod deque;
+pub mod persistent;
+pub mod unsafe_deque;
 
 /// rust linked list
-pub struct List {
-    head: Link,
+pub struct List<T> {
+    head: Link<T>,
 }
 
 /// 为什么不直接用Node：栈长度无法在编译期确定
 /// 为什么不用引用：需要提供给外界使用 引用失效
 /// 所以可以这么认为：Option就是null Box就是指针
-type Link = Option<Box<Node>>;
+type Link<T> = Option<Box<Node<T>>>;
 
-struct Node {
-    elem: i32,
-    next: Link,
+struct Node<T> {
+    elem: T,
+    next: Link<T>,
 }
 
-impl List {
+impl<T> List<T> {
     pub fn new() -> Self {
         List { head: None }
     }
 
-    pub fn push(&mut self, elem: i32) {
+    pub fn push(&mut self, elem: T) {
         // let new_node = Box::new(Node {
         //     elem: elem,
         //     next: self.head, // 这里报错 可以用clone方法解决 但是会导致整个链表的复制
@@ -34,40 +52,244 @@ impl List {
 
         let new_node = Box::new(Node {
             elem,
-            next: std::mem::replace(&mut self.head, None), // 这里进行了原子操作 就没有上面的问题了 可以优化为self.head.take()
+            // 早期写法是 std::mem::replace(&mut self.head, None) 原子地取出旧值并放入 None
+            // take 是它的简写
+            next: self.head.take(),
         });
 
         self.head = Some(new_node);
     }
 
-    pub fn pop(&mut self) -> Option<i32> {
-        // 同理这里也可以优化
-        // self.head.take().map(|node| {
-        //     self.head = node.next;
-        //     node.elem
-        // })
-        match std::mem::replace(&mut self.head, None) {
-            None => None,
-            Some(node) => {
-                self.head = node.next;
-                Some(node.elem)
-            }
+    pub fn pop(&mut self) -> Option<T> {
+        // 等价于 match self.head.take() { None => None, Some(node) => ... }
+        self.head.take().map(|node| {
+            self.head = node.next;
+            node.elem
+        })
+    }
+
+    /// 注意这里用 as_ref 将 &Option<Box<Node>> 转换为 Option<&Box<Node>>
+    /// 否则 map 会获取 head 的所有权
+    pub fn peek(&self) -> Option<&T> {
+        self.head.as_ref().map(|node| &node.elem)
+    }
+
+    pub fn peek_mut(&mut self) -> Option<&mut T> {
+        self.head.as_mut().map(|node| &mut node.elem)
+    }
+
+    /// 链表不缓存长度 需要遍历一遍
+    pub fn len(&self) -> usize {
+        self.iter().count()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.head.is_none()
+    }
+
+    pub fn contains(&self, elem: &T) -> bool
+    where
+        T: PartialEq,
+    {
+        self.iter().any(|e| e == elem)
+    }
+
+    /// 原地反转 只移动指针不移动元素
+    pub fn reverse(&mut self) {
+        let mut reversed: Link<T> = None;
+        let mut cur_link = self.head.take();
+        while let Some(mut node) = cur_link {
+            cur_link = node.next.take();
+            node.next = reversed;
+            reversed = Some(node);
         }
+        self.head = reversed;
+    }
+
+    pub fn iter(&self) -> Iter<'_, T> {
+        Iter {
+            next: self.head.as_deref(),
+        }
+    }
+
+    pub fn iter_mut(&mut self) -> IterMut<'_, T> {
+        IterMut {
+            next: self.head.as_deref_mut(),
+        }
+    }
+}
+
+impl<T> Default for List<T> {
+    fn default() -> Self {
+        Self::new()
     }
 }
 
-impl Drop for List {
+impl<T> Drop for List<T> {
     /// 因为涉及到Box<> 他会在self.ptr.drop(); 后调用deallocate(self.ptr); 因此不是尾递归可能会爆栈
     ///
     /// 大概是这个意思 所以需要手动实现drop方法
     fn drop(&mut self) {
-        let mut cur_link = mem::replace(&mut self.head, None);
+        let mut cur_link = self.head.take();
         while let Some(mut boxed_node) = cur_link {
-            cur_link = mem::replace(&mut boxed_node.next, None);
+            cur_link = boxed_node.next.take();
+        }
+    }
+}
+
+/// 获取所有权的迭代器 直接复用 pop 即可
+pub struct IntoIter<T>(List<T>);
+
+impl<T> Iterator for IntoIter<T> {
+    type Item = T;
+    fn next(&mut self) -> Option<Self::Item> {
+        self.0.pop()
+    }
+}
+
+impl<T> IntoIterator for List<T> {
+    type Item = T;
+    type IntoIter = IntoIter<T>;
+    fn into_iter(self) -> Self::IntoIter {
+        IntoIter(self)
+    }
+}
+
+/// 不可变借用的迭代器 需要标注生命周期：迭代器不能比链表活得更久
+pub struct Iter<'a, T> {
+    next: Option<&'a Node<T>>,
+}
+
+impl<'a, T> Iterator for Iter<'a, T> {
+    type Item = &'a T;
+    fn next(&mut self) -> Option<Self::Item> {
+        // Option<&T> 是 Copy 的 所以这里不需要 take
+        self.next.map(|node| {
+            self.next = node.next.as_deref();
+            &node.elem
+        })
+    }
+}
+
+impl<'a, T> IntoIterator for &'a List<T> {
+    type Item = &'a T;
+    type IntoIter = Iter<'a, T>;
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter()
+    }
+}
+
+/// 可变借用的迭代器
+pub struct IterMut<'a, T> {
+    next: Option<&'a mut Node<T>>,
+}
+
+impl<'a, T> Iterator for IterMut<'a, T> {
+    type Item = &'a mut T;
+    fn next(&mut self) -> Option<Self::Item> {
+        // &mut 不是 Copy 的 必须 take 出来 保证同一时刻只有一个可变引用
+        self.next.take().map(|node| {
+            self.next = node.next.as_deref_mut();
+            &mut node.elem
+        })
+    }
+}
+
+impl<'a, T> IntoIterator for &'a mut List<T> {
+    type Item = &'a mut T;
+    type IntoIter = IterMut<'a, T>;
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter_mut()
+    }
+}
+
+/// 按栈的语义依次 push 因此后加入的元素在前
+impl<T> Extend<T> for List<T> {
+    fn extend<I: IntoIterator<Item = T>>(&mut self, iter: I) {
+        for elem in iter {
+            self.push(elem);
         }
     }
 }
 
+/// 与 Extend 不同 collect 保持原有顺序 即 `list.iter()` 与源迭代器顺序一致
+impl<T> FromIterator<T> for List<T> {
+    fn from_iter<I: IntoIterator<Item = T>>(iter: I) -> Self {
+        let mut list = List::new();
+        list.extend(iter);
+        list.reverse();
+        list
+    }
+}
+
+/// 不能直接 derive：Node 的 Clone 会递归 长链表同样会爆栈
+impl<T: Clone> Clone for List<T> {
+    fn clone(&self) -> Self {
+        self.iter().cloned().collect()
+    }
+}
+
+impl<T: PartialEq> PartialEq for List<T> {
+    fn eq(&self, other: &Self) -> bool {
+        self.iter().eq(other.iter())
+    }
+}
+
+impl<T: Eq> Eq for List<T> {}
+
+impl<T: fmt::Debug> fmt::Debug for List<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_list().entries(self.iter()).finish()
+    }
+}
+
+/// 链表的简单演示
+pub fn test_all(out: &mut dyn Write) -> io::Result<()> {
+    writeln!(out, "\n\ncourse 5:")?;
+    let mut list = List::new();
+    list.push(1);
+    list.push(2);
+    list.push(3);
+    writeln!(out, "list: {:?}, len: {}", list, list.len())?;
+    list.reverse();
+    writeln!(out, "reversed: {:?}", list)?;
+    while let Some(elem) = list.pop() {
+        writeln!(out, "pop: {}", elem)?;
+    }
+
+    let words: List<String> = ["hello", "linked", "list"]
+        .iter()
+        .map(|s| s.to_string())
+        .collect();
+    writeln!(
+        out,
+        "collect: {:?}, contains \"list\": {}",
+        words,
+        words.contains(&"list".to_string())
+    )?;
+
+    let base = persistent::List::new().prepend(2).prepend(1);
+    let other = base.tail().prepend(42);
+    writeln!(
+        out,
+        "persistent: base {:?}, other {:?} (shared tail)",
+        base.iter().collect::<Vec<_>>(),
+        other.iter().collect::<Vec<_>>()
+    )?;
+
+    let mut deque = unsafe_deque::Deque::new();
+    deque.push_back(2);
+    deque.push_front(1);
+    deque.push_back(3);
+    writeln!(
+        out,
+        "deque: {:?}, reversed: {:?}",
+        deque.iter().collect::<Vec<_>>(),
+        deque.iter().rev().collect::<Vec<_>>()
+    )?;
+    Ok(())
+}
+
 #[cfg(test)]
 mod test {
     use super::List;
@@ -100,4 +322,82 @@ mod test {
         assert_eq!(list.pop(), Some(1));
         assert_eq!(list.pop(), None);
     }
-}
\ No newline at end of file
+
+    #[test]
+    fn peek() {
+        let mut list = List::new();
+        assert_eq!(list.peek(), None);
+        assert_eq!(list.peek_mut(), None);
+        list.push(1);
+        list.push(2);
+
+        assert_eq!(list.peek(), Some(&2));
+        if let Some(value) = list.peek_mut() {
+            *value = 42;
+        }
+        assert_eq!(list.peek(), Some(&42));
+        assert_eq!(list.pop(), Some(42));
+    }
+
+    #[test]
+    fn iterators() {
+        let mut list: List<i32> = (1..=3).collect();
+
+        assert_eq!(list.iter().collect::<Vec<_>>(), [&1, &2, &3]);
+
+        for elem in &mut list {
+            *elem *= 10;
+        }
+        assert_eq!(list.iter().copied().collect::<Vec<_>>(), [10, 20, 30]);
+
+        assert_eq!(list.into_iter().collect::<Vec<_>>(), [10, 20, 30]);
+    }
+
+    #[test]
+    fn extend_and_collect() {
+        let mut list: List<i32> = vec![1, 2].into_iter().collect();
+        assert_eq!(format!("{:?}", list), "[1, 2]");
+
+        // extend 按栈语义 后加入的在前
+        list.extend([3, 4]);
+        assert_eq!(format!("{:?}", list), "[4, 3, 1, 2]");
+    }
+
+    #[test]
+    fn len_contains_reverse() {
+        let mut list: List<&str> = ["a", "b", "c"].into_iter().collect();
+        assert_eq!(list.len(), 3);
+        assert!(!list.is_empty());
+        assert!(list.contains(&"b"));
+        assert!(!list.contains(&"d"));
+
+        list.reverse();
+        assert_eq!(list.iter().copied().collect::<Vec<_>>(), ["c", "b", "a"]);
+
+        let mut empty: List<&str> = List::new();
+        empty.reverse();
+        assert_eq!(empty.len(), 0);
+        assert!(empty.is_empty());
+    }
+
+    #[test]
+    fn clone_and_eq() {
+        let list: List<String> = ["x", "y"].iter().map(|s| s.to_string()).collect();
+        let mut cloned = list.clone();
+        assert_eq!(list, cloned);
+
+        cloned.push("z".to_string());
+        assert_ne!(list, cloned);
+        assert_eq!(list.len(), 2);
+    }
+
+    /// 长链表的 drop 和 clone 都不能递归
+    #[test]
+    fn long_list_no_stack_overflow() {
+        let list: List<u32> = (0..1_000_000).collect();
+        let cloned = list.clone();
+        assert_eq!(list, cloned);
+        drop(list);
+        drop(cloned);
+    }
+}
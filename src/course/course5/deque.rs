@@ -0,0 +1,328 @@
+//! 双端队列（安全版本）
+//! 每个节点同时被前一个节点和后一个节点引用 所以需要共享所有权 Rc
+//! 又因为要修改节点的指针 需要内部可变性 RefCell
+//!
+//! 代价：
+//! - 每次访问都有运行时的借用检查
+//! - peek 只能返回 `Ref<T>` 而不是 `&T`（借用的生命周期由 RefCell 在运行时管理）
+//! - 也因此 iter 不能直接返回 `Ref<'a, T>`：下一个节点的 Ref 只能从当前节点的 Ref 中借出 活不过当前节点
+//!   这里的 iter 改为返回持有节点 Rc 的 [`Elem`] 用的时候再 borrow 不提供 iter_mut
+//! - 节点间互相引用形成环 引用计数无法自动释放 必须手动实现 Drop
+
+use std::cell::{Ref, RefCell, RefMut};
+use std::marker::PhantomData;
+use std::rc::Rc;
+
+pub struct Deque<T> {
+    head: Link<T>,
+    tail: Link<T>,
+    len: usize,
+}
+
+type Link<T> = Option<Rc<RefCell<Node<T>>>>;
+
+struct Node<T> {
+    elem: T,
+    next: Link<T>,
+    prev: Link<T>,
+}
+
+impl<T> Node<T> {
+    fn new(elem: T) -> Rc<RefCell<Self>> {
+        Rc::new(RefCell::new(Node {
+            elem,
+            prev: None,
+            next: None,
+        }))
+    }
+}
+
+impl<T> Deque<T> {
+    pub fn new() -> Self {
+        Deque {
+            head: None,
+            tail: None,
+            len: 0,
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    pub fn push_front(&mut self, elem: T) {
+        let new_head = Node::new(elem);
+        match self.head.take() {
+            Some(old_head) => {
+                old_head.borrow_mut().prev = Some(new_head.clone());
+                new_head.borrow_mut().next = Some(old_head);
+                self.head = Some(new_head);
+            }
+            None => {
+                // 空队列 头尾指向同一个节点
+                self.tail = Some(new_head.clone());
+                self.head = Some(new_head);
+            }
+        }
+        self.len += 1;
+    }
+
+    pub fn push_back(&mut self, elem: T) {
+        let new_tail = Node::new(elem);
+        match self.tail.take() {
+            Some(old_tail) => {
+                old_tail.borrow_mut().next = Some(new_tail.clone());
+                new_tail.borrow_mut().prev = Some(old_tail);
+                self.tail = Some(new_tail);
+            }
+            None => {
+                self.head = Some(new_tail.clone());
+                self.tail = Some(new_tail);
+            }
+        }
+        self.len += 1;
+    }
+
+    pub fn pop_front(&mut self) -> Option<T> {
+        self.head.take().map(|old_head| {
+            match old_head.borrow_mut().next.take() {
+                Some(new_head) => {
+                    new_head.borrow_mut().prev.take();
+                    self.head = Some(new_head);
+                }
+                None => {
+                    self.tail.take();
+                }
+            }
+            self.len -= 1;
+            // 此时已经没有其他节点引用 old_head 了 可以安全地取出所有权
+            Self::into_elem(old_head)
+        })
+    }
+
+    pub fn pop_back(&mut self) -> Option<T> {
+        self.tail.take().map(|old_tail| {
+            match old_tail.borrow_mut().prev.take() {
+                Some(new_tail) => {
+                    new_tail.borrow_mut().next.take();
+                    self.tail = Some(new_tail);
+                }
+                None => {
+                    self.head.take();
+                }
+            }
+            self.len -= 1;
+            Self::into_elem(old_tail)
+        })
+    }
+
+    /// Rc -> RefCell -> Node -> elem 一层层取出所有权
+    fn into_elem(node: Rc<RefCell<Node<T>>>) -> T {
+        match Rc::try_unwrap(node) {
+            Ok(node) => node.into_inner().elem,
+            Err(_) => unreachable!("popped node is still shared"),
+        }
+    }
+
+    /// `Ref::map` 把节点的借用映射成元素的借用
+    pub fn peek_front(&self) -> Option<Ref<'_, T>> {
+        self.head
+            .as_ref()
+            .map(|node| Ref::map(node.borrow(), |node| &node.elem))
+    }
+
+    pub fn peek_back(&self) -> Option<Ref<'_, T>> {
+        self.tail
+            .as_ref()
+            .map(|node| Ref::map(node.borrow(), |node| &node.elem))
+    }
+
+    pub fn peek_front_mut(&mut self) -> Option<RefMut<'_, T>> {
+        self.head
+            .as_ref()
+            .map(|node| RefMut::map(node.borrow_mut(), |node| &mut node.elem))
+    }
+
+    pub fn peek_back_mut(&mut self) -> Option<RefMut<'_, T>> {
+        self.tail
+            .as_ref()
+            .map(|node| RefMut::map(node.borrow_mut(), |node| &mut node.elem))
+    }
+
+    /// 从两端都可以遍历 迭代期间队列被共享借用 不能 push / pop
+    pub fn iter(&self) -> Iter<'_, T> {
+        Iter {
+            front: self.head.clone(),
+            back: self.tail.clone(),
+            len: self.len,
+            _deque: PhantomData,
+        }
+    }
+}
+
+impl<T> Default for Deque<T> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// 节点之间 prev / next 互相持有 引用计数永远不会归零
+/// 逐个 pop 打破循环引用（同时也避免了递归 drop）
+impl<T> Drop for Deque<T> {
+    fn drop(&mut self) {
+        while self.pop_front().is_some() {}
+    }
+}
+
+/// 迭代器返回的元素 持有节点的 Rc 生命周期不超过队列的借用
+pub struct Elem<'a, T> {
+    node: Rc<RefCell<Node<T>>>,
+    _deque: PhantomData<&'a Deque<T>>,
+}
+
+/// Elem 和 Iter 都持有节点的 Rc 如果在它们 drop 之前 pop 了节点 `into_elem` 就会失败
+/// 没有 Drop 实现时 借用检查器认为 drop 不会用到 'a 共享借用在最后一次使用后就结束了
+/// 显式实现 Drop 让共享借用持续到 drop 为止 保证存活期间队列不能被修改
+impl<T> Drop for Elem<'_, T> {
+    fn drop(&mut self) {}
+}
+
+impl<T> Elem<'_, T> {
+    pub fn borrow(&self) -> Ref<'_, T> {
+        Ref::map(self.node.borrow(), |node| &node.elem)
+    }
+}
+
+pub struct Iter<'a, T> {
+    front: Link<T>,
+    back: Link<T>,
+    /// 两端相遇时停止 否则会重复返回同一个节点
+    len: usize,
+    _deque: PhantomData<&'a Deque<T>>,
+}
+
+impl<'a, T> Iterator for Iter<'a, T> {
+    type Item = Elem<'a, T>;
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.len == 0 {
+            return None;
+        }
+        let node = self.front.take()?;
+        self.front = node.borrow().next.clone();
+        self.len -= 1;
+        Some(Elem {
+            node,
+            _deque: PhantomData,
+        })
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (self.len, Some(self.len))
+    }
+}
+
+impl<T> DoubleEndedIterator for Iter<'_, T> {
+    fn next_back(&mut self) -> Option<Self::Item> {
+        if self.len == 0 {
+            return None;
+        }
+        let node = self.back.take()?;
+        self.back = node.borrow().prev.clone();
+        self.len -= 1;
+        Some(Elem {
+            node,
+            _deque: PhantomData,
+        })
+    }
+}
+
+/// 原因同 Elem 的 Drop
+impl<T> Drop for Iter<'_, T> {
+    fn drop(&mut self) {}
+}
+
+impl<T> ExactSizeIterator for Iter<'_, T> {}
+
+impl<'a, T> IntoIterator for &'a Deque<T> {
+    type Item = Elem<'a, T>;
+    type IntoIter = Iter<'a, T>;
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter()
+    }
+}
+
+pub struct IntoIter<T>(Deque<T>);
+
+impl<T> Iterator for IntoIter<T> {
+    type Item = T;
+    fn next(&mut self) -> Option<Self::Item> {
+        self.0.pop_front()
+    }
+}
+
+impl<T> DoubleEndedIterator for IntoIter<T> {
+    fn next_back(&mut self) -> Option<Self::Item> {
+        self.0.pop_back()
+    }
+}
+
+impl<T> IntoIterator for Deque<T> {
+    type Item = T;
+    type IntoIter = IntoIter<T>;
+    fn into_iter(self) -> Self::IntoIter {
+        IntoIter(self)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::Deque;
+
+    deque_test_suite!(Deque);
+
+    #[test]
+    fn iter_both_ends() {
+        let mut deque = Deque::new();
+        for i in 1..=4 {
+            deque.push_back(i);
+        }
+        let values: Vec<i32> = deque.iter().map(|elem| *elem.borrow()).collect();
+        assert_eq!(values, [1, 2, 3, 4]);
+        let values: Vec<i32> = deque.iter().rev().map(|elem| *elem.borrow()).collect();
+        assert_eq!(values, [4, 3, 2, 1]);
+
+        // 两端交替取 相遇后结束
+        {
+            let mut iter = deque.iter();
+            assert_eq!(iter.len(), 4);
+            assert_eq!(*iter.next().unwrap().borrow(), 1);
+            assert_eq!(*iter.next_back().unwrap().borrow(), 4);
+            assert_eq!(*iter.next_back().unwrap().borrow(), 3);
+            assert_eq!(*iter.next().unwrap().borrow(), 2);
+            assert!(iter.next().is_none());
+            assert!(iter.next_back().is_none());
+        }
+
+        // 遍历结束后队列仍然完整
+        assert_eq!(deque.len(), 4);
+        assert_eq!(deque.into_iter().collect::<Vec<_>>(), [1, 2, 3, 4]);
+        assert_eq!(Deque::<i32>::new().iter().count(), 0);
+    }
+
+    /// 运行时借用检查：peek 返回的 Ref 存活期间不能再可变借用同一个节点
+    #[test]
+    fn runtime_borrow_check() {
+        let mut deque = Deque::new();
+        deque.push_back(1);
+
+        let head = deque.head.clone().unwrap();
+        let peeked = deque.peek_front().unwrap();
+        assert!(head.try_borrow_mut().is_err());
+        drop(peeked);
+        assert!(head.try_borrow_mut().is_ok());
+    }
+}
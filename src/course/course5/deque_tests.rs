@@ -0,0 +1,143 @@
+//! 双端队列的公共测试用例
+//! 安全版本和不安全版本的接口一致 用宏生成同一套测试 保证两者行为相同
+
+/// 在调用处生成测试 `$deque` 为实现了双端队列接口的类型
+macro_rules! deque_test_suite {
+    ($deque:ident) => {
+        #[test]
+        fn basics_front() {
+            let mut deque = $deque::new();
+            assert_eq!(deque.pop_front(), None);
+            assert!(deque.is_empty());
+
+            deque.push_front(1);
+            deque.push_front(2);
+            deque.push_front(3);
+            assert_eq!(deque.len(), 3);
+
+            assert_eq!(deque.pop_front(), Some(3));
+            assert_eq!(deque.pop_front(), Some(2));
+
+            deque.push_front(4);
+            deque.push_front(5);
+            assert_eq!(deque.pop_front(), Some(5));
+            assert_eq!(deque.pop_front(), Some(4));
+
+            assert_eq!(deque.pop_front(), Some(1));
+            assert_eq!(deque.pop_front(), None);
+            assert_eq!(deque.len(), 0);
+        }
+
+        #[test]
+        fn basics_back() {
+            let mut deque = $deque::new();
+            assert_eq!(deque.pop_back(), None);
+
+            deque.push_back(1);
+            deque.push_back(2);
+            deque.push_back(3);
+            assert_eq!(deque.pop_back(), Some(3));
+            assert_eq!(deque.pop_back(), Some(2));
+
+            deque.push_back(4);
+            deque.push_back(5);
+            assert_eq!(deque.pop_back(), Some(5));
+            assert_eq!(deque.pop_back(), Some(4));
+
+            assert_eq!(deque.pop_back(), Some(1));
+            assert_eq!(deque.pop_back(), None);
+        }
+
+        #[test]
+        fn mixed_ends() {
+            let mut deque = $deque::new();
+            deque.push_back(2);
+            deque.push_front(1);
+            deque.push_back(3);
+            assert_eq!(deque.len(), 3);
+
+            assert_eq!(deque.pop_back(), Some(3));
+            assert_eq!(deque.pop_front(), Some(1));
+            // 只剩一个元素时 头尾指向同一个节点
+            assert_eq!(deque.pop_back(), Some(2));
+            assert_eq!(deque.pop_front(), None);
+            assert_eq!(deque.pop_back(), None);
+
+            deque.push_front(4);
+            assert_eq!(deque.pop_back(), Some(4));
+            assert!(deque.is_empty());
+        }
+
+        #[test]
+        fn peek() {
+            let mut deque = $deque::new();
+            assert!(deque.peek_front().is_none());
+            assert!(deque.peek_back().is_none());
+            assert!(deque.peek_front_mut().is_none());
+            assert!(deque.peek_back_mut().is_none());
+
+            deque.push_front(1);
+            deque.push_front(2);
+            deque.push_front(3);
+            assert_eq!(*deque.peek_front().unwrap(), 3);
+            assert_eq!(*deque.peek_back().unwrap(), 1);
+
+            *deque.peek_front_mut().unwrap() = 30;
+            *deque.peek_back_mut().unwrap() = 10;
+            assert_eq!(*deque.peek_front().unwrap(), 30);
+            assert_eq!(*deque.peek_back().unwrap(), 10);
+            assert_eq!(deque.pop_front(), Some(30));
+            assert_eq!(deque.pop_back(), Some(10));
+        }
+
+        #[test]
+        fn into_iter_double_ended() {
+            let mut deque = $deque::new();
+            for i in 1..=5 {
+                deque.push_back(i);
+            }
+
+            let mut iter = deque.into_iter();
+            assert_eq!(iter.next(), Some(1));
+            assert_eq!(iter.next_back(), Some(5));
+            assert_eq!(iter.next(), Some(2));
+            assert_eq!(iter.next_back(), Some(4));
+            assert_eq!(iter.next(), Some(3));
+            assert_eq!(iter.next_back(), None);
+            assert_eq!(iter.next(), None);
+
+            let mut deque = $deque::new();
+            for i in 1..=3 {
+                deque.push_back(i);
+            }
+            assert_eq!(deque.into_iter().rev().collect::<Vec<_>>(), [3, 2, 1]);
+        }
+
+        /// 未取出的元素要在 drop 时正确释放
+        #[test]
+        fn drop_releases_elements() {
+            use std::rc::Rc;
+
+            let tracker = Rc::new(());
+            let mut deque = $deque::new();
+            for _ in 0..10 {
+                deque.push_back(Rc::clone(&tracker));
+            }
+            deque.pop_front();
+            deque.pop_back();
+            assert_eq!(Rc::strong_count(&tracker), 9);
+
+            drop(deque);
+            assert_eq!(Rc::strong_count(&tracker), 1);
+        }
+
+        #[test]
+        fn long_deque_no_stack_overflow() {
+            let mut deque = $deque::new();
+            for i in 0..100_000 {
+                deque.push_back(i);
+            }
+            drop(deque);
+        }
+    };
+}
@@ -0,0 +1,297 @@
+//! 持久化链表（不可变 共享尾部）
+//! 与 [`super::List`] 不同 这里的节点可以有多个所有者：
+//! `prepend` 不修改原链表 而是返回一个新链表 新旧链表共享同一段尾部
+//!
+//! ```text
+//! list1 = A -> B -> C -> D
+//! list2 = tail(list1) = B -> C -> D
+//! list3 = list2.prepend(X) = X -> B -> C -> D
+//! ```
+//!
+//! 多个所有者意味着不能再用 Box（唯一所有权） 需要引用计数 Rc
+//! 代价是节点只能共享读 不再提供 peek_mut / iter_mut
+
+use std::rc::Rc;
+use std::sync::Arc;
+
+/// 单线程版本 基于 Rc
+pub struct List<T> {
+    head: Link<T>,
+}
+
+type Link<T> = Option<Rc<Node<T>>>;
+
+struct Node<T> {
+    elem: T,
+    next: Link<T>,
+}
+
+impl<T> List<T> {
+    pub fn new() -> Self {
+        List { head: None }
+    }
+
+    /// 返回一个在头部追加了元素的新链表 原链表不变
+    /// 这里的 clone 只是增加引用计数 不会复制节点
+    pub fn prepend(&self, elem: T) -> List<T> {
+        List {
+            head: Some(Rc::new(Node {
+                elem,
+                next: self.head.clone(),
+            })),
+        }
+    }
+
+    /// 返回去掉头部之后的链表 与原链表共享节点
+    pub fn tail(&self) -> List<T> {
+        List {
+            head: self.head.as_ref().and_then(|node| node.next.clone()),
+        }
+    }
+
+    pub fn head(&self) -> Option<&T> {
+        self.head.as_ref().map(|node| &node.elem)
+    }
+
+    pub fn iter(&self) -> Iter<'_, T> {
+        Iter {
+            next: self.head.as_deref(),
+        }
+    }
+}
+
+impl<T> Default for List<T> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// 与 `super::List::drop` 一样需要手动展开 避免长链表递归 drop 爆栈
+///
+/// 区别在于节点可能还被其他链表持有：
+/// 只有当前链表是最后一个所有者时（`Rc::try_unwrap` 成功）才能继续向后释放
+/// 否则说明剩余的尾部还在被共享 直接停止即可
+impl<T> Drop for List<T> {
+    fn drop(&mut self) {
+        let mut head = self.head.take();
+        while let Some(node) = head {
+            if let Ok(mut node) = Rc::try_unwrap(node) {
+                head = node.next.take();
+            } else {
+                break;
+            }
+        }
+    }
+}
+
+pub struct Iter<'a, T> {
+    next: Option<&'a Node<T>>,
+}
+
+impl<'a, T> Iterator for Iter<'a, T> {
+    type Item = &'a T;
+    fn next(&mut self) -> Option<Self::Item> {
+        self.next.map(|node| {
+            self.next = node.next.as_deref();
+            &node.elem
+        })
+    }
+}
+
+/// 多线程版本 基于 Arc
+///
+/// Rc 的引用计数不是原子的 因此 Rc 既不是 Send 也不是 Sync 不能跨线程共享
+/// 换成原子引用计数的 Arc 之后 只要 T 是 Send + Sync 整个链表就可以跨线程共享
+/// 其余实现与 [`List`] 完全一致
+pub struct SyncList<T> {
+    head: SyncLink<T>,
+}
+
+type SyncLink<T> = Option<Arc<SyncNode<T>>>;
+
+struct SyncNode<T> {
+    elem: T,
+    next: SyncLink<T>,
+}
+
+impl<T> SyncList<T> {
+    pub fn new() -> Self {
+        SyncList { head: None }
+    }
+
+    pub fn prepend(&self, elem: T) -> SyncList<T> {
+        SyncList {
+            head: Some(Arc::new(SyncNode {
+                elem,
+                next: self.head.clone(),
+            })),
+        }
+    }
+
+    pub fn tail(&self) -> SyncList<T> {
+        SyncList {
+            head: self.head.as_ref().and_then(|node| node.next.clone()),
+        }
+    }
+
+    pub fn head(&self) -> Option<&T> {
+        self.head.as_ref().map(|node| &node.elem)
+    }
+
+    pub fn iter(&self) -> SyncIter<'_, T> {
+        SyncIter {
+            next: self.head.as_deref(),
+        }
+    }
+}
+
+impl<T> Default for SyncList<T> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// 共享链表在多线程中也可以 clone 出新的所有者
+impl<T> Clone for SyncList<T> {
+    fn clone(&self) -> Self {
+        SyncList {
+            head: self.head.clone(),
+        }
+    }
+}
+
+impl<T> Drop for SyncList<T> {
+    fn drop(&mut self) {
+        let mut head = self.head.take();
+        while let Some(node) = head {
+            // 两个线程同时释放最后两个所有者时 `Arc::try_unwrap` 可能在两边都失败
+            // 剩下的尾部会退回递归释放 into_inner 保证恰好有一个线程拿到节点
+            match Arc::into_inner(node) {
+                Some(mut node) => head = node.next.take(),
+                None => break,
+            }
+        }
+    }
+}
+
+pub struct SyncIter<'a, T> {
+    next: Option<&'a SyncNode<T>>,
+}
+
+impl<'a, T> Iterator for SyncIter<'a, T> {
+    type Item = &'a T;
+    fn next(&mut self) -> Option<Self::Item> {
+        self.next.map(|node| {
+            self.next = node.next.as_deref();
+            &node.elem
+        })
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use std::sync::Barrier;
+    use std::thread;
+
+    #[test]
+    fn basics() {
+        let list = List::new();
+        assert_eq!(list.head(), None);
+
+        let list = list.prepend(1).prepend(2).prepend(3);
+        assert_eq!(list.head(), Some(&3));
+
+        let list = list.tail();
+        assert_eq!(list.head(), Some(&2));
+
+        let list = list.tail();
+        assert_eq!(list.head(), Some(&1));
+
+        let list = list.tail();
+        assert_eq!(list.head(), None);
+
+        // Make sure empty tail works
+        let list = list.tail();
+        assert_eq!(list.head(), None);
+    }
+
+    #[test]
+    fn iter() {
+        let list = List::new().prepend(1).prepend(2).prepend(3);
+        assert_eq!(list.iter().collect::<Vec<_>>(), [&3, &2, &1]);
+    }
+
+    /// 新旧链表共享同一段尾部 且互不影响
+    #[test]
+    fn shared_tail() {
+        let base = List::new().prepend("c").prepend("b");
+        let left = base.prepend("a");
+        let right = base.prepend("x");
+
+        assert_eq!(left.iter().copied().collect::<Vec<_>>(), ["a", "b", "c"]);
+        assert_eq!(right.iter().copied().collect::<Vec<_>>(), ["x", "b", "c"]);
+
+        // 三个链表指向同一个 "b" 节点：base 自己 + left 和 right 的 next
+        let shared = base.head.as_ref().unwrap();
+        assert_eq!(Rc::strong_count(shared), 3);
+
+        drop(left);
+        drop(base);
+        assert_eq!(right.iter().count(), 3);
+    }
+
+    #[test]
+    fn long_list_no_stack_overflow() {
+        let mut list = List::new();
+        for i in 0..1_000_000 {
+            list = list.prepend(i);
+        }
+        let shared = list.tail();
+        drop(list);
+        assert_eq!(shared.head(), Some(&999_998));
+        drop(shared);
+
+        let mut list = SyncList::new();
+        for i in 0..1_000_000 {
+            list = list.prepend(i);
+        }
+        drop(list);
+    }
+
+    #[test]
+    fn concurrent_drop_of_last_owners() {
+        for _ in 0..4 {
+            let mut list = SyncList::new();
+            for i in 0..200_000 {
+                list = list.prepend(i);
+            }
+            let barrier = Barrier::new(2);
+            thread::scope(|s| {
+                for list in [list.clone(), list] {
+                    let barrier = &barrier;
+                    s.spawn(move || {
+                        barrier.wait();
+                        drop(list);
+                    });
+                }
+            });
+        }
+    }
+
+    #[test]
+    fn sync_list_across_threads() {
+        let list = SyncList::new().prepend(1).prepend(2).prepend(3);
+
+        let handles: Vec<_> = (0..4)
+            .map(|i| {
+                let list = list.clone();
+                thread::spawn(move || list.prepend(i * 10).iter().sum::<i32>())
+            })
+            .collect();
+
+        let sums: Vec<i32> = handles.into_iter().map(|h| h.join().unwrap()).collect();
+        assert_eq!(sums, [6, 16, 26, 36]);
+        assert_eq!(list.tail().iter().collect::<Vec<_>>(), [&2, &1]);
+    }
+}
@@ -0,0 +1,363 @@
+//! 双端队列（不安全版本）
+//! 用裸指针代替 `Rc<RefCell<_>>`：没有引用计数和运行时借用检查 可以直接返回 `&T`
+//! 也因此能提供 iter / iter_mut
+//!
+//! 所有的 unsafe 都集中在这个模块内部 对外暴露的接口是安全的
+//! 不变式（invariant）：
+//! - 非空时 front 和 back 都指向有效的节点 空队列时两者都是 None
+//! - 每个节点都由 `Box::into_raw` 分配 且只会被 `Box::from_raw` 释放一次
+//!
+//! 用 Miri 检查未定义行为（需要 nightly 工具链 首次运行会自动安装 rust-src 仓库没有 CI 需要手动运行）
+//! ```text
+//! rustup +nightly component add miri
+//! cargo +nightly miri test course5::unsafe_deque
+//! ```
+//! 本模块的 10 个测试在 Miri 下全部通过（解释执行较慢 约两分钟）
+
+use std::marker::PhantomData;
+use std::ptr::NonNull;
+
+pub struct Deque<T> {
+    front: Link<T>,
+    back: Link<T>,
+    len: usize,
+    /// 告诉编译器 Deque 逻辑上拥有 T（影响 drop check 和协变）
+    _marker: PhantomData<T>,
+}
+
+/// NonNull 相比 `*mut T` 保证非空 且是协变的
+type Link<T> = Option<NonNull<Node<T>>>;
+
+struct Node<T> {
+    front: Link<T>,
+    back: Link<T>,
+    elem: T,
+}
+
+impl<T> Deque<T> {
+    pub fn new() -> Self {
+        Deque {
+            front: None,
+            back: None,
+            len: 0,
+            _marker: PhantomData,
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    pub fn push_front(&mut self, elem: T) {
+        // SAFETY: new 刚由 Box 分配 old 满足不变式 都是有效指针
+        unsafe {
+            let new = NonNull::new_unchecked(Box::into_raw(Box::new(Node {
+                front: None,
+                back: None,
+                elem,
+            })));
+            match self.front {
+                Some(old) => {
+                    (*old.as_ptr()).front = Some(new);
+                    (*new.as_ptr()).back = Some(old);
+                }
+                None => self.back = Some(new),
+            }
+            self.front = Some(new);
+        }
+        self.len += 1;
+    }
+
+    pub fn push_back(&mut self, elem: T) {
+        // SAFETY: 同 push_front
+        unsafe {
+            let new = NonNull::new_unchecked(Box::into_raw(Box::new(Node {
+                front: None,
+                back: None,
+                elem,
+            })));
+            match self.back {
+                Some(old) => {
+                    (*old.as_ptr()).back = Some(new);
+                    (*new.as_ptr()).front = Some(old);
+                }
+                None => self.front = Some(new),
+            }
+            self.back = Some(new);
+        }
+        self.len += 1;
+    }
+
+    pub fn pop_front(&mut self) -> Option<T> {
+        // SAFETY: node 满足不变式 且从链表中摘下后不再被其他地方引用 可以还给 Box 释放
+        self.front.map(|node| unsafe {
+            let boxed = Box::from_raw(node.as_ptr());
+            self.front = boxed.back;
+            match self.front {
+                Some(new) => (*new.as_ptr()).front = None,
+                None => self.back = None,
+            }
+            self.len -= 1;
+            boxed.elem
+        })
+    }
+
+    pub fn pop_back(&mut self) -> Option<T> {
+        // SAFETY: 同 pop_front
+        self.back.map(|node| unsafe {
+            let boxed = Box::from_raw(node.as_ptr());
+            self.back = boxed.front;
+            match self.back {
+                Some(new) => (*new.as_ptr()).back = None,
+                None => self.front = None,
+            }
+            self.len -= 1;
+            boxed.elem
+        })
+    }
+
+    /// 返回的引用与 &self 的生命周期绑定 借用检查器会阻止在此期间修改队列
+    pub fn peek_front(&self) -> Option<&T> {
+        // SAFETY: 不变式保证指针有效
+        self.front.map(|node| unsafe { &(*node.as_ptr()).elem })
+    }
+
+    pub fn peek_back(&self) -> Option<&T> {
+        // SAFETY: 同 peek_front
+        self.back.map(|node| unsafe { &(*node.as_ptr()).elem })
+    }
+
+    pub fn peek_front_mut(&mut self) -> Option<&mut T> {
+        // SAFETY: &mut self 保证这是唯一的可变引用
+        self.front.map(|node| unsafe { &mut (*node.as_ptr()).elem })
+    }
+
+    pub fn peek_back_mut(&mut self) -> Option<&mut T> {
+        // SAFETY: 同 peek_front_mut
+        self.back.map(|node| unsafe { &mut (*node.as_ptr()).elem })
+    }
+
+    pub fn iter(&self) -> Iter<'_, T> {
+        Iter {
+            front: self.front,
+            back: self.back,
+            len: self.len,
+            _marker: PhantomData,
+        }
+    }
+
+    pub fn iter_mut(&mut self) -> IterMut<'_, T> {
+        IterMut {
+            front: self.front,
+            back: self.back,
+            len: self.len,
+            _marker: PhantomData,
+        }
+    }
+}
+
+impl<T> Default for Deque<T> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<T> Drop for Deque<T> {
+    fn drop(&mut self) {
+        while self.pop_front().is_some() {}
+    }
+}
+
+// SAFETY: 裸指针默认既不是 Send 也不是 Sync
+// Deque 独占所有节点 行为与 Box<T> 相同 因此可以按 T 的情况实现
+unsafe impl<T: Send> Send for Deque<T> {}
+unsafe impl<T: Sync> Sync for Deque<T> {}
+
+/// 双端迭代器 用剩余长度 len 判断两端是否相遇
+pub struct Iter<'a, T> {
+    front: Link<T>,
+    back: Link<T>,
+    len: usize,
+    _marker: PhantomData<&'a T>,
+}
+
+impl<'a, T> Iterator for Iter<'a, T> {
+    type Item = &'a T;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.len == 0 {
+            return None;
+        }
+        // SAFETY: len > 0 时 front 一定指向队列中尚未迭代的有效节点
+        self.front.map(|node| unsafe {
+            self.len -= 1;
+            self.front = (*node.as_ptr()).back;
+            &(*node.as_ptr()).elem
+        })
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (self.len, Some(self.len))
+    }
+}
+
+impl<T> DoubleEndedIterator for Iter<'_, T> {
+    fn next_back(&mut self) -> Option<Self::Item> {
+        if self.len == 0 {
+            return None;
+        }
+        // SAFETY: 同 next
+        self.back.map(|node| unsafe {
+            self.len -= 1;
+            self.back = (*node.as_ptr()).front;
+            &(*node.as_ptr()).elem
+        })
+    }
+}
+
+impl<T> ExactSizeIterator for Iter<'_, T> {}
+
+impl<'a, T> IntoIterator for &'a Deque<T> {
+    type Item = &'a T;
+    type IntoIter = Iter<'a, T>;
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter()
+    }
+}
+
+pub struct IterMut<'a, T> {
+    front: Link<T>,
+    back: Link<T>,
+    len: usize,
+    _marker: PhantomData<&'a mut T>,
+}
+
+impl<'a, T> Iterator for IterMut<'a, T> {
+    type Item = &'a mut T;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.len == 0 {
+            return None;
+        }
+        // SAFETY: len 保证两端不会交叉 每个节点只会被返回一次 可变引用不会重复
+        self.front.map(|node| unsafe {
+            self.len -= 1;
+            self.front = (*node.as_ptr()).back;
+            &mut (*node.as_ptr()).elem
+        })
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (self.len, Some(self.len))
+    }
+}
+
+impl<T> DoubleEndedIterator for IterMut<'_, T> {
+    fn next_back(&mut self) -> Option<Self::Item> {
+        if self.len == 0 {
+            return None;
+        }
+        // SAFETY: 同 next
+        self.back.map(|node| unsafe {
+            self.len -= 1;
+            self.back = (*node.as_ptr()).front;
+            &mut (*node.as_ptr()).elem
+        })
+    }
+}
+
+impl<T> ExactSizeIterator for IterMut<'_, T> {}
+
+impl<'a, T> IntoIterator for &'a mut Deque<T> {
+    type Item = &'a mut T;
+    type IntoIter = IterMut<'a, T>;
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter_mut()
+    }
+}
+
+pub struct IntoIter<T>(Deque<T>);
+
+impl<T> Iterator for IntoIter<T> {
+    type Item = T;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.0.pop_front()
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (self.0.len, Some(self.0.len))
+    }
+}
+
+impl<T> DoubleEndedIterator for IntoIter<T> {
+    fn next_back(&mut self) -> Option<Self::Item> {
+        self.0.pop_back()
+    }
+}
+
+impl<T> ExactSizeIterator for IntoIter<T> {}
+
+impl<T> IntoIterator for Deque<T> {
+    type Item = T;
+    type IntoIter = IntoIter<T>;
+    fn into_iter(self) -> Self::IntoIter {
+        IntoIter(self)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::Deque;
+
+    deque_test_suite!(Deque);
+
+    #[test]
+    fn iter_double_ended() {
+        let mut deque = Deque::new();
+        for i in 1..=4 {
+            deque.push_back(i);
+        }
+
+        let mut iter = deque.iter();
+        assert_eq!(iter.len(), 4);
+        assert_eq!(iter.next(), Some(&1));
+        assert_eq!(iter.next_back(), Some(&4));
+        assert_eq!(iter.next_back(), Some(&3));
+        assert_eq!(iter.next(), Some(&2));
+        assert_eq!(iter.next(), None);
+        assert_eq!(iter.next_back(), None);
+
+        assert_eq!(deque.iter().rev().collect::<Vec<_>>(), [&4, &3, &2, &1]);
+    }
+
+    #[test]
+    fn iter_mut() {
+        let mut deque = Deque::new();
+        for i in 1..=4 {
+            deque.push_front(i);
+        }
+
+        for elem in &mut deque {
+            *elem *= 10;
+        }
+        let mut iter = deque.iter_mut();
+        *iter.next_back().unwrap() += 1;
+        *iter.next().unwrap() += 2;
+        assert_eq!(iter.len(), 2);
+
+        assert_eq!(deque.into_iter().collect::<Vec<_>>(), [42, 30, 20, 11]);
+    }
+
+    #[test]
+    fn send_across_threads() {
+        let mut deque = Deque::new();
+        deque.push_back(String::from("moved"));
+        let handle = std::thread::spawn(move || deque.pop_front());
+        assert_eq!(handle.join().unwrap().as_deref(), Some("moved"));
+    }
+}
@@ -1,4 +1,48 @@
 //! rust的异常处理
+//!
+//! 本章学习：
+//! - panic 与 catch_unwind
+//! - Result 的几种处理写法 以及 ? 语法糖
+//! - 自定义错误类型 Display / Error / source() 错误链（见 errors）
+//! - 用 From 在不同层的错误之间转换 以及带 backtrace 的动态错误
+
+pub mod errors;
+
+use std::io::{self, Write};
+
+/// 异常处理的简单演示 更多写法见单元测试
+pub fn test_all(out: &mut dyn Write) -> io::Result<()> {
+    writeln!(out, "\n\ncourse 6:")?;
+    fn afunc(t: bool) -> Result<String, String> {
+        if t {
+            Ok("yes!".to_string())
+        } else {
+            Err("nono!".to_string())
+        }
+    }
+
+    for t in [true, false] {
+        match afunc(t) {
+            Ok(info) => writeln!(out, "ok: {info}")?,
+            Err(error_info) => writeln!(out, "err: {error_info}")?,
+        }
+    }
+    writeln!(out, "catch_unwind: {:?}", std::panic::catch_unwind(|| 0).is_ok())?;
+
+    let configs = [
+        "port = 8080\nworkers = 4",
+        "port = 8080\nworkers = x",
+        "port = 80\nworkers = 1",
+    ];
+    for config in configs {
+        match errors::run(config.as_bytes(), None) {
+            Ok(config) => writeln!(out, "config: {config:?}")?,
+            // {:#} 输出整个错误链
+            Err(report) => writeln!(out, "error: {report:#}")?,
+        }
+    }
+    Ok(())
+}
 
 #[cfg(test)]
 mod error_handle {
@@ -19,6 +63,8 @@ mod error_handle {
     #[test]
     fn panic_catch() {
         assert!(std::panic::catch_unwind(|| { 0 }).is_ok());
+        // 闭包确实 panic 时返回 Err 章节运行器（见 runner）就是这样隔离每个章节的
+        assert!(std::panic::catch_unwind(|| panic!("oops")).is_err());
 
         // // 注意 std::panic::catch_unwind 只能捕获 unwind stack 形式的 panic
         // let result = std::panic::catch_unwind(|| { 0 });
@@ -29,6 +75,7 @@ mod error_handle {
     }
 
     #[test]
+    #[allow(clippy::unnecessary_unwrap, clippy::needless_return)] // 演示各种写法
     fn result_error() {
         fn afunc(t: bool) -> Result<String, String> {
             if t {
@@ -73,6 +120,9 @@ mod error_handle {
             let a_info = afunc(false)?; // 异常相当直接 return E
             return Ok(a_info);
         }
-        println!("use ?: {}", inner().unwrap_or("result is Err, default error".to_string()));
+        println!(
+            "use ?: {}",
+            inner().unwrap_or("result is Err, default error".to_string())
+        );
     }
 }
@@ -0,0 +1,377 @@
+//! 分层的错误类型
+//! 以“读取配置 -> 启动程序”为例 每一层有自己的错误类型
+//! - 底层：std 的 io::Error ParseIntError
+//! - 配置层：[`ConfigError`] 包装底层错误 通过 source() 保留错误链
+//! - 应用层：[`AppError`] 包装配置层错误
+//! - 最外层：[`Report`] 装箱的动态错误 附带 backtrace 类似于 anyhow::Error
+//!
+//! 每一层都为下一层实现 From 所以可以直接用 `?` 向上传递
+
+use std::backtrace::{Backtrace, BacktraceStatus};
+use std::error::Error;
+use std::fmt;
+use std::io::{self, Read};
+use std::num::ParseIntError;
+
+/// 配置
+#[derive(Debug, PartialEq)]
+pub struct Config {
+    pub port: u16,
+    pub workers: u8,
+}
+
+/// 配置层错误
+#[derive(Debug)]
+pub enum ConfigError {
+    Io(io::Error),
+    /// 数字解析失败 记录是哪个配置项
+    Parse {
+        key: String,
+        source: ParseIntError,
+    },
+    Missing(&'static str),
+    /// 格式错误的行 行号从 1 开始
+    Syntax(usize),
+}
+
+impl fmt::Display for ConfigError {
+    /// 只描述本层的错误 底层原因交给 source() 避免重复输出
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ConfigError::Io(_) => write!(f, "failed to read config"),
+            ConfigError::Parse { key, .. } => write!(f, "invalid value for {key}"),
+            ConfigError::Missing(key) => write!(f, "missing config key {key}"),
+            ConfigError::Syntax(line) => write!(f, "syntax error at line {line}"),
+        }
+    }
+}
+
+impl Error for ConfigError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            ConfigError::Io(err) => Some(err),
+            ConfigError::Parse { source, .. } => Some(source),
+            ConfigError::Missing(_) | ConfigError::Syntax(_) => None,
+        }
+    }
+}
+
+/// 有了这个实现 读取配置时可以直接对 io::Result 使用 `?`
+impl From<io::Error> for ConfigError {
+    fn from(err: io::Error) -> Self {
+        ConfigError::Io(err)
+    }
+}
+
+/// 应用层错误
+#[derive(Debug)]
+pub enum AppError {
+    Config(ConfigError),
+    /// 命令行传入的端口无法解析
+    InvalidPort(ParseIntError),
+    /// 解析成功但不合理的配置
+    Invalid(String),
+}
+
+impl fmt::Display for AppError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            AppError::Config(_) => write!(f, "failed to load config"),
+            AppError::InvalidPort(_) => write!(f, "invalid port argument"),
+            AppError::Invalid(reason) => write!(f, "invalid config: {reason}"),
+        }
+    }
+}
+
+impl Error for AppError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            AppError::Config(err) => Some(err),
+            AppError::InvalidPort(err) => Some(err),
+            AppError::Invalid(_) => None,
+        }
+    }
+}
+
+impl From<ConfigError> for AppError {
+    fn from(err: ConfigError) -> Self {
+        AppError::Config(err)
+    }
+}
+
+impl From<ParseIntError> for AppError {
+    fn from(err: ParseIntError) -> Self {
+        AppError::InvalidPort(err)
+    }
+}
+
+/// 装箱的动态错误 任何错误都可以用 `?` 转换成它 同时在转换时捕获 backtrace
+///
+/// 注意 Report 本身不能实现 Error：
+/// 否则 `impl<E: Error> From<E> for Report` 会与标准库的 `impl<T> From<T> for T` 冲突（anyhow 也是这样处理的）
+pub struct Report {
+    error: Box<dyn Error + Send + Sync + 'static>,
+    backtrace: Backtrace,
+}
+
+impl Report {
+    /// 只有设置了 `RUST_BACKTRACE=1` 或 `RUST_LIB_BACKTRACE=1` 时才会真正捕获
+    pub fn backtrace(&self) -> &Backtrace {
+        &self.backtrace
+    }
+
+    /// 从最外层开始 依次遍历整个错误链
+    pub fn chain(&self) -> impl Iterator<Item = &(dyn Error + 'static)> {
+        let first: &(dyn Error + 'static) = self.error.as_ref();
+        std::iter::successors(Some(first), |&err| err.source())
+    }
+
+    /// 取回最外层错误的具体类型
+    pub fn downcast_ref<E: Error + 'static>(&self) -> Option<&E> {
+        self.error.downcast_ref()
+    }
+
+    /// 错误链中最底层的错误
+    pub fn root_cause(&self) -> &(dyn Error + 'static) {
+        self.chain().last().expect("chain is never empty")
+    }
+}
+
+impl<E: Error + Send + Sync + 'static> From<E> for Report {
+    fn from(error: E) -> Self {
+        Report {
+            error: Box::new(error),
+            backtrace: Backtrace::capture(),
+        }
+    }
+}
+
+/// `{}` 只输出最外层 `{:#}` 输出整个错误链
+impl fmt::Display for Report {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if f.alternate() {
+            let messages: Vec<String> = self.chain().map(|err| err.to_string()).collect();
+            write!(f, "{}", messages.join(": "))
+        } else {
+            write!(f, "{}", self.error)
+        }
+    }
+}
+
+/// main 返回 Err 时使用 Debug 输出 所以这里输出错误链和 backtrace
+impl fmt::Debug for Report {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.error)?;
+        for (i, cause) in self.chain().skip(1).enumerate() {
+            write!(f, "\n  {i}: {cause}")?;
+        }
+        if self.backtrace.status() == BacktraceStatus::Captured {
+            write!(f, "\n\nbacktrace:\n{}", self.backtrace)?;
+        }
+        Ok(())
+    }
+}
+
+/// 解析 `key = value` 格式的配置 `#` 开头的是注释
+pub fn parse_config(text: &str) -> Result<Config, ConfigError> {
+    let mut port = None;
+    let mut workers = None;
+    for (i, line) in text.lines().enumerate() {
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+        let (key, value) = line.split_once('=').ok_or(ConfigError::Syntax(i + 1))?;
+        let (key, value) = (key.trim(), value.trim());
+        // 这里需要附加配置项名字 所以不能依赖 From 而是用 map_err
+        let parse_err = |source| ConfigError::Parse {
+            key: key.to_string(),
+            source,
+        };
+        match key {
+            "port" => port = Some(value.parse().map_err(parse_err)?),
+            "workers" => workers = Some(value.parse().map_err(parse_err)?),
+            _ => {} // 忽略未知配置项
+        }
+    }
+    Ok(Config {
+        port: port.ok_or(ConfigError::Missing("port"))?,
+        workers: workers.ok_or(ConfigError::Missing("workers"))?,
+    })
+}
+
+/// io::Error -> ConfigError
+pub fn load_config(mut reader: impl Read) -> Result<Config, ConfigError> {
+    let mut text = String::new();
+    reader.read_to_string(&mut text)?;
+    parse_config(&text)
+}
+
+/// ConfigError -> AppError 和 ParseIntError -> AppError
+pub fn start(reader: impl Read, port_arg: Option<&str>) -> Result<Config, AppError> {
+    let mut config = load_config(reader)?;
+    if let Some(port) = port_arg {
+        config.port = port.parse()?;
+    }
+    if config.workers == 0 {
+        return Err(AppError::Invalid("workers must be positive".into()));
+    }
+    Ok(config)
+}
+
+/// AppError -> Report 以及任意错误 -> Report
+pub fn run(reader: impl Read, port_arg: Option<&str>) -> Result<Config, Report> {
+    let config = start(reader, port_arg)?;
+    if config.port < 1024 {
+        // 临时的错误也可以直接装箱：&str -> Box<dyn Error + Send + Sync> 由标准库提供
+        let err: Box<dyn Error + Send + Sync> = "privileged port".into();
+        return Err(Report {
+            error: err,
+            backtrace: Backtrace::capture(),
+        });
+    }
+    Ok(config)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    /// 总是失败的 Reader 用来模拟 io 错误
+    struct Broken;
+
+    impl Read for Broken {
+        fn read(&mut self, _buf: &mut [u8]) -> io::Result<usize> {
+            Err(io::Error::other("disk on fire"))
+        }
+    }
+
+    const GOOD: &str = "# server\nport = 8080\nworkers = 4\n";
+
+    fn messages(err: &(dyn Error + 'static)) -> Vec<String> {
+        std::iter::successors(Some(err), |&err| err.source())
+            .map(|err| err.to_string())
+            .collect()
+    }
+
+    #[test]
+    fn parse_ok() {
+        let config = Config {
+            port: 8080,
+            workers: 4,
+        };
+        assert_eq!(parse_config(GOOD).unwrap(), config);
+        assert_eq!(load_config(GOOD.as_bytes()).unwrap(), config);
+        assert_eq!(start(GOOD.as_bytes(), None).unwrap(), config);
+        assert_eq!(run(GOOD.as_bytes(), Some("9000")).unwrap().port, 9000);
+    }
+
+    #[test]
+    fn config_errors() {
+        let err = parse_config("port = 80\nworkers").unwrap_err();
+        assert!(matches!(err, ConfigError::Syntax(2)));
+        assert!(err.source().is_none());
+
+        let err = parse_config("port = 8080").unwrap_err();
+        assert_eq!(err.to_string(), "missing config key workers");
+
+        let err = parse_config("port = 99999\nworkers = 1").unwrap_err();
+        assert!(matches!(&err, ConfigError::Parse { key, .. } if key == "port"));
+        assert_eq!(
+            messages(&err),
+            [
+                "invalid value for port",
+                "number too large to fit in target type"
+            ]
+        );
+    }
+
+    /// io::Error -> ConfigError
+    #[test]
+    fn io_to_config() {
+        let err = load_config(Broken).unwrap_err();
+        assert!(matches!(err, ConfigError::Io(_)));
+        assert_eq!(messages(&err), ["failed to read config", "disk on fire"]);
+
+        // 非法 UTF-8 同样是 io 错误
+        let err = load_config(&[0xff, 0xfe][..]).unwrap_err();
+        let io_err = err.source().unwrap().downcast_ref::<io::Error>().unwrap();
+        assert_eq!(io_err.kind(), io::ErrorKind::InvalidData);
+    }
+
+    /// ConfigError -> AppError
+    #[test]
+    fn config_to_app() {
+        let err = start(Broken, None).unwrap_err();
+        assert!(matches!(err, AppError::Config(ConfigError::Io(_))));
+        assert_eq!(
+            messages(&err),
+            [
+                "failed to load config",
+                "failed to read config",
+                "disk on fire"
+            ]
+        );
+
+        let err = start("port = 1".as_bytes(), None).unwrap_err();
+        assert!(matches!(
+            err,
+            AppError::Config(ConfigError::Missing("workers"))
+        ));
+    }
+
+    /// ParseIntError -> AppError
+    #[test]
+    fn parse_int_to_app() {
+        let err = start(GOOD.as_bytes(), Some("http")).unwrap_err();
+        assert!(matches!(err, AppError::InvalidPort(_)));
+        assert_eq!(
+            messages(&err),
+            ["invalid port argument", "invalid digit found in string"]
+        );
+
+        let err = start("port = 1\nworkers = 0".as_bytes(), None).unwrap_err();
+        assert!(matches!(err, AppError::Invalid(_)));
+        assert!(err.source().is_none());
+    }
+
+    /// AppError -> Report 以及 &str -> Report
+    #[test]
+    fn app_to_report() {
+        let report = run(Broken, None).unwrap_err();
+        assert!(report.downcast_ref::<AppError>().is_some());
+        assert_eq!(report.chain().count(), 3);
+        assert_eq!(report.root_cause().to_string(), "disk on fire");
+        assert_eq!(report.to_string(), "failed to load config");
+        assert_eq!(
+            format!("{report:#}"),
+            "failed to load config: failed to read config: disk on fire"
+        );
+        assert!(format!("{report:?}").contains("\n  1: disk on fire"));
+        // 是否捕获取决于环境变量
+        assert!(matches!(
+            report.backtrace().status(),
+            BacktraceStatus::Captured | BacktraceStatus::Disabled
+        ));
+
+        let report = run(GOOD.as_bytes(), Some("80")).unwrap_err();
+        assert!(report.downcast_ref::<AppError>().is_none());
+        assert_eq!(report.to_string(), "privileged port");
+        assert_eq!(report.chain().count(), 1);
+    }
+
+    /// 任何实现了 Error + Send + Sync 的类型都可以用 ? 转换为 Report
+    #[test]
+    fn any_error_to_report() {
+        fn parse(input: &str) -> Result<u8, Report> {
+            Ok(input.parse::<u8>()?)
+        }
+        let report = parse("256").unwrap_err();
+        assert!(report.downcast_ref::<ParseIntError>().is_some());
+
+        fn read() -> Result<Config, Report> {
+            Ok(load_config(Broken)?)
+        }
+        assert!(read().unwrap_err().downcast_ref::<ConfigError>().is_some());
+    }
+}
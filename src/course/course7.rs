@@ -1,5 +1,31 @@
 //! trait 特性（类似于接口）
 
+use std::io::{self, Write};
+
+/// 特性的简单演示 详细说明见单元测试
+pub fn test_all(out: &mut dyn Write) -> io::Result<()> {
+    writeln!(out, "\n\ncourse 7:")?;
+    trait Runner {
+        fn run(&self) -> String;
+        fn run_default(&self) -> String {
+            "run_default".to_string()
+        }
+    }
+
+    struct Aser(&'static str);
+
+    impl Runner for Aser {
+        fn run(&self) -> String {
+            format!("run: {}", self.0)
+        }
+    }
+
+    let a = Aser("inner info");
+    writeln!(out, "{}", a.run())?;
+    writeln!(out, "{}", a.run_default())?;
+    Ok(())
+}
+
 #[cfg(test)]
 mod test_trait {
 
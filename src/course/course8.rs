@@ -1,5 +1,135 @@
 //! 多线程并发
-//! rust支持协程，但只定义了基本语法，具体实现交给了社区，这里没举例
+//! rust支持协程，但只定义了基本语法（async / await / Future），运行时交给了社区（如 tokio）
+//!
+//! 本章学习：
+//! - thread::spawn 和 join
+//! - mpsc 通道 多生产者单消费者
+//! - 用 Mutex + Condvar 实现多生产者多消费者通道（见 mpmc）
+//! - RwLock、原子类型与内存顺序、thread::scope、Barrier（见 primitives）
+//! - 线程池：任务队列、优雅关闭、panic 恢复、作用域任务（见 thread_pool）
+//! - 异步运行时：执行器、Waker、定时器、join / select、异步通道（见 async_runtime）
+
+pub mod async_runtime;
+pub mod mpmc;
+pub mod primitives;
+pub mod thread_pool;
+
+use std::io::{self, Write};
+use std::{sync::mpsc, thread};
+
+/// 线程和通道的简单演示 详细说明见单元测试
+pub fn test_all(out: &mut dyn Write) -> io::Result<()> {
+    writeln!(out, "\n\ncourse 8:")?;
+    let (sender, receiver) = mpsc::channel();
+    let handles: Vec<_> = (0..3)
+        .map(|i| {
+            let sender = sender.clone();
+            thread::spawn(move || sender.send(i * i).unwrap())
+        })
+        .collect();
+    drop(sender);
+    for handle in handles {
+        handle.join().unwrap();
+    }
+
+    let mut received: Vec<i32> = receiver.iter().collect();
+    received.sort();
+    writeln!(out, "received from threads: {:?}", received)?;
+
+    let pool = thread_pool::ThreadPool::new(4);
+    let handles: Vec<_> = (1..=5).map(|i| pool.spawn(move || i * 10)).collect();
+    let results: Vec<i32> = handles.into_iter().filter_map(|h| h.join().ok()).collect();
+    writeln!(out, "thread pool results: {:?}", results)?;
+
+    let words = ["thread", "pool", "scope"];
+    let lengths = pool.scope(|s| {
+        let handles: Vec<_> = words.iter().map(|w| s.spawn(move || w.len())).collect();
+        handles
+            .into_iter()
+            .map(|h| h.join().unwrap())
+            .collect::<Vec<_>>()
+    });
+    writeln!(out, "scoped jobs borrowed {:?}: {:?}", words, lengths)?;
+
+    use async_runtime::{block_on, channel, join, yield_now};
+    let (sender, mut receiver) = channel();
+    let log = block_on(async move {
+        let producer = async move {
+            for i in 0..3 {
+                sender.send(i).unwrap();
+                yield_now().await;
+            }
+        };
+        let consumer = async move {
+            let mut log = Vec::new();
+            while let Some(i) = receiver.recv().await {
+                log.push(format!("recv {i}"));
+            }
+            log
+        };
+        join(producer, consumer).await.1
+    });
+    writeln!(out, "async channel: {:?}", log)?;
+
+    // 两个生产者 两个消费者
+    let (sender, receiver) = mpmc::bounded(2);
+    let consumers: Vec<_> = (0..2)
+        .map(|_| {
+            let receiver = receiver.clone();
+            thread::spawn(move || receiver.iter().collect::<Vec<i32>>())
+        })
+        .collect();
+    drop(receiver);
+    for range in [0..3, 3..6] {
+        let sender = sender.clone();
+        thread::spawn(move || range.for_each(|i| sender.send(i).unwrap()));
+    }
+    drop(sender);
+    let mut received: Vec<i32> = consumers
+        .into_iter()
+        .flat_map(|c| c.join().unwrap())
+        .collect();
+    received.sort();
+    writeln!(out, "mpmc received: {:?}", received)?;
+
+    let data: Vec<u64> = (1..=100).collect();
+    let sum = primitives::map_reduce(&data, 4, 0, |x| x * x, |a, b| a + b);
+    writeln!(out, "map_reduce sum of squares 1..=100: {}", sum)?;
+
+    let cache = primitives::LruCache::new(2);
+    cache.insert("one", 1);
+    cache.insert("two", 2);
+    cache.get(&"one");
+    let evicted = cache.insert("three", 3);
+    writeln!(out, "lru evicted: {:?}, stats: {:?}", evicted, cache.stats())?;
+
+    let counter = primitives::Counter::new();
+    let lock = primitives::SpinLock::new(0);
+    thread::scope(|s| {
+        for _ in 0..4 {
+            s.spawn(|| {
+                for _ in 0..100 {
+                    counter.increment();
+                    *lock.lock() += 1;
+                }
+            });
+        }
+    });
+    writeln!(
+        out,
+        "atomic counter: {}, spin lock: {}",
+        counter.get(),
+        lock.into_inner()
+    )?;
+
+    let events = primitives::simulate_phases(3, 2);
+    let done: Vec<_> = events
+        .iter()
+        .filter(|event| matches!(event, primitives::PhaseEvent::Done(_)))
+        .collect();
+    writeln!(out, "barrier phases: {} events, {:?}", events.len(), done)?;
+    Ok(())
+}
 
 #[cfg(test)]
 mod test_concurrent {
@@ -28,7 +158,7 @@ mod test_concurrent {
     fn test_channel() {
         // Multiple Producer Single Consumer
         // 多生产者（可以克隆给多个线程使用） 单消费者
-        // 相对的 golang 则是 mpmc
+        // 相对的 golang 则是 mpmc（手写的 mpmc 通道见 mpmc 模块）
         let (sender, receiver) = mpsc::channel();
 
         thread::spawn(move || {
@@ -42,5 +172,5 @@ mod test_concurrent {
         println!("Got: {}", received);
     }
 
-    // todo async and tokio
+    // 异步的例子见 async_runtime
 }
@@ -0,0 +1,711 @@
+//! 最小的异步运行时
+//! 标准库只提供了 Future / Waker 等基础设施 这里不依赖 tokio 展示它们是怎样配合的
+//! - [`Executor`]：单线程执行器 任务就绪时通过 Waker 把自己放回队列
+//! - [`block_on`]：不创建任务 直接在当前线程上 poll 一个 Future 直到完成
+//! - [`sleep`]：由后台线程实现的定时器 到期后调用 Waker 唤醒任务
+//! - [`join`] / [`select`]：同时等待两个 Future / 取先完成的一个
+//! - [`channel`]：无界异步通道 接收方在队列为空时挂起
+//!
+//! 执行器是单线程的 就绪队列先进先出 所以任务的交替顺序是确定的
+
+use std::collections::VecDeque;
+use std::future::Future;
+use std::pin::{pin, Pin};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{Arc, Condvar, Mutex, Weak};
+use std::task::{Context, Poll, Wake, Waker};
+use std::thread::{self, Thread};
+use std::time::Duration;
+
+type BoxFuture = Pin<Box<dyn Future<Output = ()> + Send>>;
+
+/// 执行器内部共享的状态
+struct Shared {
+    queue: Mutex<VecDeque<Arc<Task>>>,
+    /// 队列从空变为非空时通知 run
+    ready: Condvar,
+    /// 尚未完成的任务数
+    live: AtomicUsize,
+}
+
+impl Shared {
+    fn schedule(&self, task: Arc<Task>) {
+        self.queue.lock().unwrap().push_back(task);
+        self.ready.notify_one();
+    }
+}
+
+struct Task {
+    /// 完成后置为 None 之后的唤醒都会被忽略
+    future: Mutex<Option<BoxFuture>>,
+    /// 队列持有 Arc<Task> 这里用 Weak 避免循环引用
+    /// 执行器释放后 迟到的唤醒直接丢弃任务
+    shared: Weak<Shared>,
+}
+
+/// 实现 Wake 后 Arc<Task> 可以直接转换为 Waker
+/// 唤醒就是把任务重新放回就绪队列
+impl Wake for Task {
+    fn wake(self: Arc<Self>) {
+        if let Some(shared) = self.shared.upgrade() {
+            shared.schedule(self);
+        }
+    }
+}
+
+/// 单线程执行器
+pub struct Executor {
+    shared: Arc<Shared>,
+}
+
+impl Default for Executor {
+    fn default() -> Self {
+        Executor {
+            shared: Arc::new(Shared {
+                queue: Mutex::new(VecDeque::new()),
+                ready: Condvar::new(),
+                live: AtomicUsize::new(0),
+            }),
+        }
+    }
+}
+
+impl Executor {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// 用于在任务内部继续创建任务
+    pub fn spawner(&self) -> Spawner {
+        Spawner {
+            shared: Arc::clone(&self.shared),
+        }
+    }
+
+    pub fn spawn<F>(&self, future: F) -> JoinHandle<F::Output>
+    where
+        F: Future + Send + 'static,
+        F::Output: Send + 'static,
+    {
+        self.spawner().spawn(future)
+    }
+
+    /// 运行直到所有任务完成
+    /// 队列为空但还有任务未完成时（例如都在等定时器） 阻塞等待唤醒
+    pub fn run(&self) {
+        loop {
+            let task = {
+                let mut queue = self.shared.queue.lock().unwrap();
+                loop {
+                    if let Some(task) = queue.pop_front() {
+                        break task;
+                    }
+                    if self.shared.live.load(Ordering::SeqCst) == 0 {
+                        return;
+                    }
+                    queue = self.shared.ready.wait(queue).unwrap();
+                }
+            };
+
+            let mut slot = task.future.lock().unwrap();
+            let Some(future) = slot.as_mut() else {
+                continue; // 已完成的任务被重复唤醒
+            };
+            let waker = Waker::from(Arc::clone(&task));
+            if future
+                .as_mut()
+                .poll(&mut Context::from_waker(&waker))
+                .is_ready()
+            {
+                *slot = None;
+                self.shared.live.fetch_sub(1, Ordering::SeqCst);
+            }
+        }
+    }
+}
+
+/// 丢弃还在队列中的任务
+/// 任务中可能持有 Spawner（进而持有 Shared） 只靠引用计数无法释放
+impl Drop for Executor {
+    fn drop(&mut self) {
+        loop {
+            // 先取出再释放 释放 future 时可能唤醒其它任务 不能持有锁
+            let tasks = std::mem::take(&mut *self.shared.queue.lock().unwrap());
+            if tasks.is_empty() {
+                break;
+            }
+            for task in tasks {
+                task.future.lock().unwrap().take();
+            }
+        }
+    }
+}
+
+/// 可以克隆并移动到任务中的 spawn 句柄
+#[derive(Clone)]
+pub struct Spawner {
+    shared: Arc<Shared>,
+}
+
+impl Spawner {
+    pub fn spawn<F>(&self, future: F) -> JoinHandle<F::Output>
+    where
+        F: Future + Send + 'static,
+        F::Output: Send + 'static,
+    {
+        let slot = Arc::new(Mutex::new(Slot {
+            value: None,
+            waker: None,
+        }));
+        let result = Arc::clone(&slot);
+        let future = async move {
+            let value = future.await;
+            let mut slot = result.lock().unwrap();
+            slot.value = Some(value);
+            if let Some(waker) = slot.waker.take() {
+                waker.wake();
+            }
+        };
+
+        self.shared.live.fetch_add(1, Ordering::SeqCst);
+        self.shared.schedule(Arc::new(Task {
+            future: Mutex::new(Some(Box::pin(future))),
+            shared: Arc::downgrade(&self.shared),
+        }));
+        JoinHandle { slot }
+    }
+}
+
+struct Slot<T> {
+    value: Option<T>,
+    waker: Option<Waker>,
+}
+
+/// 任务的结果 本身也是一个 Future 可以在其它任务中 await
+pub struct JoinHandle<T> {
+    slot: Arc<Mutex<Slot<T>>>,
+}
+
+impl<T> JoinHandle<T> {
+    /// 非异步地取回结果 任务未完成时返回 None
+    pub fn try_take(&self) -> Option<T> {
+        self.slot.lock().unwrap().value.take()
+    }
+}
+
+impl<T> Future for JoinHandle<T> {
+    type Output = T;
+
+    fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<T> {
+        let mut slot = self.slot.lock().unwrap();
+        match slot.value.take() {
+            Some(value) => Poll::Ready(value),
+            None => {
+                slot.waker = Some(cx.waker().clone());
+                Poll::Pending
+            }
+        }
+    }
+}
+
+/// 唤醒时 unpark 对应的线程
+struct ThreadWaker(Thread);
+
+impl Wake for ThreadWaker {
+    fn wake(self: Arc<Self>) {
+        self.0.unpark();
+    }
+}
+
+/// 在当前线程上运行 Future 直到完成 不需要创建任务 也就没有 Send / 'static 的限制
+/// （可以借用栈上的数据 inversion 的异步处理函数就是这样运行的）
+/// - poll 返回 Pending 时挂起当前线程（park）
+/// - Future 就绪后通过 Waker 唤醒线程（unpark） 再 poll 一次
+pub fn block_on<F: Future>(future: F) -> F::Output {
+    // Future 被 poll 之后不能再移动 所以需要先固定（pin）在栈上
+    let mut future = pin!(future);
+    let waker = Waker::from(Arc::new(ThreadWaker(thread::current())));
+    let mut cx = Context::from_waker(&waker);
+    loop {
+        match future.as_mut().poll(&mut cx) {
+            Poll::Ready(output) => return output,
+            // park 可能被虚假唤醒 没关系 再 poll 一次即可
+            Poll::Pending => thread::park(),
+        }
+    }
+}
+
+/// 让出一次执行权 当前任务排到就绪队列末尾
+pub fn yield_now() -> YieldNow {
+    YieldNow { yielded: false }
+}
+
+pub struct YieldNow {
+    yielded: bool,
+}
+
+impl Future for YieldNow {
+    type Output = ();
+
+    fn poll(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<()> {
+        if self.yielded {
+            return Poll::Ready(());
+        }
+        self.yielded = true;
+        cx.waker().wake_by_ref();
+        Poll::Pending
+    }
+}
+
+/// 定时器 第一次 poll 时启动后台线程 到期后唤醒任务
+pub fn sleep(duration: Duration) -> Sleep {
+    Sleep {
+        duration,
+        state: None,
+    }
+}
+
+struct SleepState {
+    done: bool,
+    waker: Option<Waker>,
+}
+
+pub struct Sleep {
+    duration: Duration,
+    state: Option<Arc<Mutex<SleepState>>>,
+}
+
+impl Future for Sleep {
+    type Output = ();
+
+    fn poll(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<()> {
+        let duration = self.duration;
+        let state = self.state.get_or_insert_with(|| {
+            let state = Arc::new(Mutex::new(SleepState {
+                done: false,
+                waker: None,
+            }));
+            let timer = Arc::clone(&state);
+            thread::spawn(move || {
+                thread::sleep(duration);
+                let mut state = timer.lock().unwrap();
+                state.done = true;
+                if let Some(waker) = state.waker.take() {
+                    waker.wake();
+                }
+            });
+            state
+        });
+
+        let mut state = state.lock().unwrap();
+        if state.done {
+            Poll::Ready(())
+        } else {
+            // 每次都更新 waker：future 可能被移动到别的任务中
+            state.waker = Some(cx.waker().clone());
+            Poll::Pending
+        }
+    }
+}
+
+/// 同时等待两个 future 都完成
+/// 两个 future 装箱固定 这样 Join 本身是 Unpin 的 不需要 unsafe 的 pin 投影
+pub fn join<A: Future, B: Future>(a: A, b: B) -> Join<A, B> {
+    Join {
+        a: Box::pin(a),
+        b: Box::pin(b),
+        a_out: None,
+        b_out: None,
+    }
+}
+
+pub struct Join<A: Future, B: Future> {
+    a: Pin<Box<A>>,
+    b: Pin<Box<B>>,
+    a_out: Option<A::Output>,
+    b_out: Option<B::Output>,
+}
+
+// 输出只会被按值取出 从不被固定 所以 Join 可以是 Unpin 的
+impl<A: Future, B: Future> Unpin for Join<A, B> {}
+
+impl<A: Future, B: Future> Future for Join<A, B> {
+    type Output = (A::Output, B::Output);
+
+    fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        let this = self.get_mut();
+        if this.a_out.is_none() {
+            if let Poll::Ready(out) = this.a.as_mut().poll(cx) {
+                this.a_out = Some(out);
+            }
+        }
+        if this.b_out.is_none() {
+            if let Poll::Ready(out) = this.b.as_mut().poll(cx) {
+                this.b_out = Some(out);
+            }
+        }
+        if this.a_out.is_some() && this.b_out.is_some() {
+            Poll::Ready((this.a_out.take().unwrap(), this.b_out.take().unwrap()))
+        } else {
+            Poll::Pending
+        }
+    }
+}
+
+#[derive(Debug, PartialEq)]
+pub enum Either<A, B> {
+    Left(A),
+    Right(B),
+}
+
+/// 等待两个 future 中先完成的一个 另一个被丢弃
+/// 同时就绪时优先返回左边
+pub fn select<A: Future, B: Future>(a: A, b: B) -> Select<A, B> {
+    Select {
+        a: Box::pin(a),
+        b: Box::pin(b),
+    }
+}
+
+pub struct Select<A, B> {
+    a: Pin<Box<A>>,
+    b: Pin<Box<B>>,
+}
+
+impl<A: Future, B: Future> Future for Select<A, B> {
+    type Output = Either<A::Output, B::Output>;
+
+    fn poll(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        if let Poll::Ready(out) = self.a.as_mut().poll(cx) {
+            return Poll::Ready(Either::Left(out));
+        }
+        if let Poll::Ready(out) = self.b.as_mut().poll(cx) {
+            return Poll::Ready(Either::Right(out));
+        }
+        Poll::Pending
+    }
+}
+
+struct ChannelState<T> {
+    queue: VecDeque<T>,
+    /// 接收方挂起时留下的 waker
+    waker: Option<Waker>,
+    senders: usize,
+    receiver_alive: bool,
+}
+
+/// 无界异步通道 多发送方单接收方
+pub fn channel<T>() -> (Sender<T>, Receiver<T>) {
+    let state = Arc::new(Mutex::new(ChannelState {
+        queue: VecDeque::new(),
+        waker: None,
+        senders: 1,
+        receiver_alive: true,
+    }));
+    (
+        Sender {
+            state: Arc::clone(&state),
+        },
+        Receiver { state },
+    )
+}
+
+pub struct Sender<T> {
+    state: Arc<Mutex<ChannelState<T>>>,
+}
+
+impl<T> Sender<T> {
+    /// 无界通道发送不需要等待 接收方已关闭时退回消息
+    pub fn send(&self, value: T) -> Result<(), T> {
+        let mut state = self.state.lock().unwrap();
+        if !state.receiver_alive {
+            return Err(value);
+        }
+        state.queue.push_back(value);
+        if let Some(waker) = state.waker.take() {
+            waker.wake();
+        }
+        Ok(())
+    }
+}
+
+impl<T> Clone for Sender<T> {
+    fn clone(&self) -> Self {
+        self.state.lock().unwrap().senders += 1;
+        Sender {
+            state: Arc::clone(&self.state),
+        }
+    }
+}
+
+impl<T> Drop for Sender<T> {
+    /// 最后一个发送方关闭时唤醒接收方 让它看到通道已关闭
+    fn drop(&mut self) {
+        let mut state = self.state.lock().unwrap();
+        state.senders -= 1;
+        if state.senders == 0 {
+            if let Some(waker) = state.waker.take() {
+                waker.wake();
+            }
+        }
+    }
+}
+
+pub struct Receiver<T> {
+    state: Arc<Mutex<ChannelState<T>>>,
+}
+
+impl<T> Receiver<T> {
+    /// 接收下一条消息 所有发送方都关闭且队列为空时返回 None
+    pub fn recv(&mut self) -> Recv<'_, T> {
+        Recv { receiver: self }
+    }
+}
+
+impl<T> Drop for Receiver<T> {
+    fn drop(&mut self) {
+        self.state.lock().unwrap().receiver_alive = false;
+    }
+}
+
+pub struct Recv<'a, T> {
+    receiver: &'a mut Receiver<T>,
+}
+
+impl<T> Future for Recv<'_, T> {
+    type Output = Option<T>;
+
+    fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<T>> {
+        let mut state = self.receiver.state.lock().unwrap();
+        if let Some(value) = state.queue.pop_front() {
+            Poll::Ready(Some(value))
+        } else if state.senders == 0 {
+            Poll::Ready(None)
+        } else {
+            state.waker = Some(cx.waker().clone());
+            Poll::Pending
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use std::cell::Cell;
+    use std::time::Instant;
+
+    type Log = Arc<Mutex<Vec<String>>>;
+
+    async fn worker(name: &'static str, steps: usize, log: Log) {
+        for i in 0..steps {
+            log.lock().unwrap().push(format!("{name}{i}"));
+            yield_now().await;
+        }
+    }
+
+    /// 由其他线程唤醒的 Future 验证执行器的 park / unpark
+    #[test]
+    fn block_on_wakes_from_other_thread() {
+        struct Timer {
+            started: bool,
+            done: Arc<std::sync::atomic::AtomicBool>,
+        }
+
+        impl Future for Timer {
+            type Output = &'static str;
+
+            fn poll(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+                use std::sync::atomic::Ordering;
+
+                if self.done.load(Ordering::Acquire) {
+                    return Poll::Ready("done");
+                }
+                if !self.started {
+                    self.started = true;
+                    let done = Arc::clone(&self.done);
+                    let waker = cx.waker().clone();
+                    thread::spawn(move || {
+                        thread::sleep(Duration::from_millis(10));
+                        done.store(true, Ordering::Release);
+                        waker.wake();
+                    });
+                }
+                Poll::Pending
+            }
+        }
+
+        let timer = Timer {
+            started: false,
+            done: Arc::default(),
+        };
+        assert_eq!(block_on(timer), "done");
+    }
+
+    #[test]
+    fn yield_now_polls_twice() {
+        let polled = Cell::new(0);
+        block_on(async {
+            polled.set(polled.get() + 1);
+            yield_now().await;
+            polled.set(polled.get() + 1);
+        });
+        assert_eq!(polled.get(), 2);
+    }
+
+    /// 单线程 FIFO 执行器上 两个任务严格交替执行
+    #[test]
+    fn tasks_interleave_deterministically() {
+        let log: Log = Arc::default();
+        let executor = Executor::new();
+        executor.spawn(worker("a", 3, Arc::clone(&log)));
+        executor.spawn(worker("b", 2, Arc::clone(&log)));
+        executor.run();
+        assert_eq!(*log.lock().unwrap(), ["a0", "b0", "a1", "b1", "a2"]);
+    }
+
+    /// 任务持有 Spawner 时形成 队列 -> 任务 -> Spawner -> 队列 的环 释放执行器时要能回收
+    #[test]
+    fn dropped_executor_frees_queued_tasks() {
+        let marker = Arc::new(());
+        let executor = Executor::new();
+        for _ in 0..3 {
+            let marker = Arc::clone(&marker);
+            let spawner = executor.spawner();
+            executor.spawn(async move {
+                let _held = (marker, spawner);
+            });
+        }
+        assert_eq!(Arc::strong_count(&marker), 4);
+        let shared = Arc::downgrade(&executor.shared);
+        drop(executor);
+        assert_eq!(Arc::strong_count(&marker), 1);
+        assert!(shared.upgrade().is_none());
+    }
+
+    /// join 在同一个任务内交替推进两个 future
+    #[test]
+    fn join_interleaves_within_task() {
+        let log: Log = Arc::default();
+        let (a, b) = (
+            worker("a", 2, Arc::clone(&log)),
+            worker("b", 2, Arc::clone(&log)),
+        );
+        block_on(async move {
+            join(a, b).await;
+        });
+        assert_eq!(*log.lock().unwrap(), ["a0", "b0", "a1", "b1"]);
+
+        assert_eq!(block_on(join(async { 1 }, async { "two" })), (1, "two"));
+    }
+
+    #[test]
+    fn join_handles_and_nested_spawn() {
+        let executor = Executor::new();
+        let spawner = executor.spawner();
+        let outer = executor.spawn(async move {
+            let inner = spawner.spawn(async { 20 });
+            yield_now().await;
+            inner.await + 1
+        });
+        assert!(outer.try_take().is_none());
+        executor.run();
+        assert_eq!(outer.try_take(), Some(21));
+    }
+
+    /// 定时器在后台线程中到期 执行器阻塞等待唤醒
+    #[test]
+    fn sleep_wakes_executor() {
+        let start = Instant::now();
+        let (a, b) = block_on(join(
+            async {
+                sleep(Duration::from_millis(30)).await;
+                "a"
+            },
+            async {
+                sleep(Duration::from_millis(30)).await;
+                "b"
+            },
+        ));
+        assert_eq!((a, b), ("a", "b"));
+        assert!(start.elapsed() >= Duration::from_millis(30));
+    }
+
+    #[test]
+    fn select_takes_first() {
+        let fast = async {
+            sleep(Duration::from_millis(5)).await;
+            "fast"
+        };
+        let slow = async {
+            sleep(Duration::from_secs(5)).await;
+            "slow"
+        };
+        assert_eq!(block_on(select(slow, fast)), Either::Right("fast"));
+
+        // 同时就绪时左边优先
+        assert_eq!(block_on(select(async { 1 }, async { 2 })), Either::Left(1));
+    }
+
+    #[test]
+    fn channel_between_tasks() {
+        let log: Log = Arc::default();
+        let executor = Executor::new();
+        let (sender, mut receiver) = channel();
+
+        let producer_log = Arc::clone(&log);
+        executor.spawn(async move {
+            for i in 0..3 {
+                producer_log.lock().unwrap().push(format!("send{i}"));
+                sender.send(i).unwrap();
+                yield_now().await;
+            }
+            // sender 在这里被丢弃 接收方随后收到 None
+        });
+        let consumer_log = Arc::clone(&log);
+        let received = executor.spawn(async move {
+            let mut received = Vec::new();
+            while let Some(value) = receiver.recv().await {
+                consumer_log.lock().unwrap().push(format!("recv{value}"));
+                received.push(value);
+            }
+            received
+        });
+        executor.run();
+
+        assert_eq!(received.try_take(), Some(vec![0, 1, 2]));
+        assert_eq!(
+            *log.lock().unwrap(),
+            ["send0", "recv0", "send1", "recv1", "send2", "recv2"]
+        );
+    }
+
+    #[test]
+    fn channel_closed_receiver() {
+        let (sender, receiver) = channel();
+        let other = sender.clone();
+        drop(receiver);
+        assert_eq!(sender.send(1), Err(1));
+        assert_eq!(other.send(2), Err(2));
+    }
+
+    /// 消息来自其它线程 接收任务被跨线程唤醒
+    #[test]
+    fn channel_from_thread() {
+        let (sender, mut receiver) = channel();
+        let producer = thread::spawn(move || {
+            for i in 0..5 {
+                sender.send(i).unwrap();
+                thread::sleep(Duration::from_millis(1));
+            }
+        });
+        let sum = block_on(async move {
+            let mut sum = 0;
+            while let Some(value) = receiver.recv().await {
+                sum += value;
+            }
+            sum
+        });
+        producer.join().unwrap();
+        assert_eq!(sum, 10);
+    }
+}
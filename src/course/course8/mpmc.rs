@@ -0,0 +1,549 @@
+//! 多生产者多消费者通道
+//! std 的 mpsc 只能有一个接收方 这里用 Mutex + Condvar 实现一个类似 golang channel 的 mpmc 通道
+//! - 有界（[`bounded`]）：队列满时 send 阻塞 对应 golang 的带缓冲 channel
+//! - 无界（[`unbounded`]）：send 从不阻塞
+//! - 断开检测：所有发送方都关闭后 接收方取完剩余消息后返回错误 反之亦然
+//! - [`select`]：同时等待多个接收方 类似 golang 的 select 语句
+//!
+//! 接口和错误类型的命名与 std::sync::mpsc 保持一致
+
+use std::collections::VecDeque;
+use std::error::Error;
+use std::fmt;
+use std::sync::{Arc, Condvar, Mutex, MutexGuard};
+use std::time::{Duration, Instant};
+
+/// select 用来等待多个通道的信号
+#[derive(Default)]
+struct Signal {
+    ready: Mutex<bool>,
+    cond: Condvar,
+}
+
+impl Signal {
+    fn notify(&self) {
+        *self.ready.lock().unwrap() = true;
+        self.cond.notify_all();
+    }
+
+    fn wait(&self) {
+        let mut ready = self.ready.lock().unwrap();
+        while !*ready {
+            ready = self.cond.wait(ready).unwrap();
+        }
+        *ready = false;
+    }
+}
+
+struct Inner<T> {
+    queue: VecDeque<T>,
+    /// None 表示无界
+    capacity: Option<usize>,
+    senders: usize,
+    receivers: usize,
+    /// 正在 select 这个通道的线程
+    watchers: Vec<Arc<Signal>>,
+}
+
+impl<T> Inner<T> {
+    fn is_full(&self) -> bool {
+        self.capacity
+            .is_some_and(|capacity| self.queue.len() >= capacity)
+    }
+
+    fn notify_watchers(&self) {
+        for watcher in &self.watchers {
+            watcher.notify();
+        }
+    }
+}
+
+struct Shared<T> {
+    inner: Mutex<Inner<T>>,
+    not_empty: Condvar,
+    not_full: Condvar,
+}
+
+impl<T> Shared<T> {
+    fn lock(&self) -> MutexGuard<'_, Inner<T>> {
+        self.inner.lock().unwrap()
+    }
+}
+
+fn channel<T>(capacity: Option<usize>) -> (Sender<T>, Receiver<T>) {
+    let shared = Arc::new(Shared {
+        inner: Mutex::new(Inner {
+            queue: VecDeque::new(),
+            capacity,
+            senders: 1,
+            receivers: 1,
+            watchers: Vec::new(),
+        }),
+        not_empty: Condvar::new(),
+        not_full: Condvar::new(),
+    });
+    (
+        Sender {
+            shared: Arc::clone(&shared),
+        },
+        Receiver { shared },
+    )
+}
+
+/// 有界通道 队列中最多有 capacity 条消息
+///
+/// # Panics
+///
+/// capacity 为 0 时 panic（这里不实现 golang 无缓冲 channel 的同步交接语义）
+pub fn bounded<T>(capacity: usize) -> (Sender<T>, Receiver<T>) {
+    assert!(capacity > 0, "capacity must be positive");
+    channel(Some(capacity))
+}
+
+pub fn unbounded<T>() -> (Sender<T>, Receiver<T>) {
+    channel(None)
+}
+
+/// 所有接收方都已关闭 退回未发送的消息
+#[derive(Debug, PartialEq)]
+pub struct SendError<T>(pub T);
+
+#[derive(Debug, PartialEq)]
+pub enum TrySendError<T> {
+    Full(T),
+    Disconnected(T),
+}
+
+/// 所有发送方都已关闭 且队列已空
+#[derive(Debug, PartialEq)]
+pub struct RecvError;
+
+#[derive(Debug, PartialEq)]
+pub enum TryRecvError {
+    Empty,
+    Disconnected,
+}
+
+#[derive(Debug, PartialEq)]
+pub enum RecvTimeoutError {
+    Timeout,
+    Disconnected,
+}
+
+impl<T> fmt::Display for SendError<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "sending on a disconnected channel")
+    }
+}
+
+impl<T> fmt::Display for TrySendError<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            TrySendError::Full(_) => write!(f, "sending on a full channel"),
+            TrySendError::Disconnected(_) => write!(f, "sending on a disconnected channel"),
+        }
+    }
+}
+
+impl fmt::Display for RecvError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "receiving on a disconnected channel")
+    }
+}
+
+impl fmt::Display for TryRecvError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            TryRecvError::Empty => write!(f, "receiving on an empty channel"),
+            TryRecvError::Disconnected => write!(f, "receiving on a disconnected channel"),
+        }
+    }
+}
+
+impl fmt::Display for RecvTimeoutError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            RecvTimeoutError::Timeout => write!(f, "timed out waiting on channel"),
+            RecvTimeoutError::Disconnected => write!(f, "receiving on a disconnected channel"),
+        }
+    }
+}
+
+impl<T: fmt::Debug> Error for SendError<T> {}
+impl<T: fmt::Debug> Error for TrySendError<T> {}
+impl Error for RecvError {}
+impl Error for TryRecvError {}
+impl Error for RecvTimeoutError {}
+
+pub struct Sender<T> {
+    shared: Arc<Shared<T>>,
+}
+
+impl<T> Sender<T> {
+    /// 放入消息并唤醒一个接收方
+    fn push(&self, mut inner: MutexGuard<'_, Inner<T>>, value: T) {
+        inner.queue.push_back(value);
+        inner.notify_watchers();
+        drop(inner);
+        self.shared.not_empty.notify_one();
+    }
+
+    /// 有界通道已满时阻塞等待
+    pub fn send(&self, value: T) -> Result<(), SendError<T>> {
+        let mut inner = self.shared.lock();
+        loop {
+            if inner.receivers == 0 {
+                return Err(SendError(value));
+            }
+            if !inner.is_full() {
+                break;
+            }
+            inner = self.shared.not_full.wait(inner).unwrap();
+        }
+        self.push(inner, value);
+        Ok(())
+    }
+
+    pub fn try_send(&self, value: T) -> Result<(), TrySendError<T>> {
+        let inner = self.shared.lock();
+        if inner.receivers == 0 {
+            Err(TrySendError::Disconnected(value))
+        } else if inner.is_full() {
+            Err(TrySendError::Full(value))
+        } else {
+            self.push(inner, value);
+            Ok(())
+        }
+    }
+}
+
+impl<T> Clone for Sender<T> {
+    fn clone(&self) -> Self {
+        self.shared.lock().senders += 1;
+        Sender {
+            shared: Arc::clone(&self.shared),
+        }
+    }
+}
+
+impl<T> Drop for Sender<T> {
+    /// 最后一个发送方关闭时 唤醒所有等待中的接收方
+    fn drop(&mut self) {
+        let mut inner = self.shared.lock();
+        inner.senders -= 1;
+        if inner.senders == 0 {
+            inner.notify_watchers();
+            drop(inner);
+            self.shared.not_empty.notify_all();
+        }
+    }
+}
+
+pub struct Receiver<T> {
+    shared: Arc<Shared<T>>,
+}
+
+impl<T> Receiver<T> {
+    /// 取出消息并唤醒一个发送方
+    fn pop(&self, inner: &mut Inner<T>) -> Option<T> {
+        let value = inner.queue.pop_front()?;
+        self.shared.not_full.notify_one();
+        Some(value)
+    }
+
+    pub fn recv(&self) -> Result<T, RecvError> {
+        let mut inner = self.shared.lock();
+        loop {
+            if let Some(value) = self.pop(&mut inner) {
+                return Ok(value);
+            }
+            if inner.senders == 0 {
+                return Err(RecvError);
+            }
+            inner = self.shared.not_empty.wait(inner).unwrap();
+        }
+    }
+
+    pub fn try_recv(&self) -> Result<T, TryRecvError> {
+        let mut inner = self.shared.lock();
+        match self.pop(&mut inner) {
+            Some(value) => Ok(value),
+            None if inner.senders == 0 => Err(TryRecvError::Disconnected),
+            None => Err(TryRecvError::Empty),
+        }
+    }
+
+    pub fn recv_timeout(&self, timeout: Duration) -> Result<T, RecvTimeoutError> {
+        let deadline = Instant::now() + timeout;
+        let mut inner = self.shared.lock();
+        loop {
+            if let Some(value) = self.pop(&mut inner) {
+                return Ok(value);
+            }
+            if inner.senders == 0 {
+                return Err(RecvTimeoutError::Disconnected);
+            }
+            // 可能被虚假唤醒或被其它接收方抢先 所以每次都重新计算剩余时间
+            let remaining = deadline.saturating_duration_since(Instant::now());
+            if remaining.is_zero() {
+                return Err(RecvTimeoutError::Timeout);
+            }
+            inner = self
+                .shared
+                .not_empty
+                .wait_timeout(inner, remaining)
+                .unwrap()
+                .0;
+        }
+    }
+
+    /// 阻塞迭代 直到通道断开
+    pub fn iter(&self) -> impl Iterator<Item = T> + '_ {
+        std::iter::from_fn(|| self.recv().ok())
+    }
+
+    fn watch(&self, signal: &Arc<Signal>) {
+        self.shared.lock().watchers.push(Arc::clone(signal));
+    }
+
+    fn unwatch(&self, signal: &Arc<Signal>) {
+        self.shared
+            .lock()
+            .watchers
+            .retain(|watcher| !Arc::ptr_eq(watcher, signal));
+    }
+}
+
+/// 与 std 不同 接收方也可以克隆 每条消息只会被其中一个接收方收到
+impl<T> Clone for Receiver<T> {
+    fn clone(&self) -> Self {
+        self.shared.lock().receivers += 1;
+        Receiver {
+            shared: Arc::clone(&self.shared),
+        }
+    }
+}
+
+impl<T> Drop for Receiver<T> {
+    /// 最后一个接收方关闭时 唤醒所有阻塞在 send 上的发送方
+    fn drop(&mut self) {
+        let mut inner = self.shared.lock();
+        inner.receivers -= 1;
+        if inner.receivers == 0 {
+            drop(inner);
+            self.shared.not_full.notify_all();
+        }
+    }
+}
+
+/// 等待多个接收方中任意一个有消息 返回 (下标, 消息)
+/// 多个同时有消息时优先返回靠前的 所有通道都断开时返回 RecvError
+pub fn select<T>(receivers: &[&Receiver<T>]) -> Result<(usize, T), RecvError> {
+    let signal = Arc::new(Signal::default());
+    // 先注册再检查 检查之后到达的消息一定会通知 signal 不会丢失唤醒
+    for receiver in receivers {
+        receiver.watch(&signal);
+    }
+    let result = loop {
+        let mut disconnected = 0;
+        let mut found = None;
+        for (i, receiver) in receivers.iter().enumerate() {
+            match receiver.try_recv() {
+                Ok(value) => {
+                    found = Some((i, value));
+                    break;
+                }
+                Err(TryRecvError::Disconnected) => disconnected += 1,
+                Err(TryRecvError::Empty) => {}
+            }
+        }
+        if let Some(found) = found {
+            break Ok(found);
+        }
+        if disconnected == receivers.len() {
+            break Err(RecvError);
+        }
+        signal.wait();
+    };
+    for receiver in receivers {
+        receiver.unwatch(&signal);
+    }
+    result
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use std::sync::mpsc;
+    use std::thread;
+
+    #[test]
+    fn unbounded_fifo() {
+        let (sender, receiver) = unbounded();
+        for i in 0..5 {
+            sender.send(i).unwrap();
+        }
+        assert_eq!(receiver.try_recv(), Ok(0));
+        drop(sender);
+        assert_eq!(receiver.iter().collect::<Vec<_>>(), [1, 2, 3, 4]);
+        assert_eq!(receiver.recv(), Err(RecvError));
+        assert_eq!(receiver.try_recv(), Err(TryRecvError::Disconnected));
+    }
+
+    #[test]
+    fn bounded_blocks_when_full() {
+        let (sender, receiver) = bounded(2);
+        sender.send(1).unwrap();
+        assert_eq!(sender.try_send(2), Ok(()));
+        assert_eq!(sender.try_send(3), Err(TrySendError::Full(3)));
+
+        // send 阻塞到接收方取走一条消息
+        let blocked = thread::spawn(move || {
+            sender.send(3).unwrap();
+        });
+        thread::sleep(Duration::from_millis(20));
+        assert!(!blocked.is_finished());
+        assert_eq!(receiver.recv(), Ok(1));
+        blocked.join().unwrap();
+        assert_eq!(receiver.iter().collect::<Vec<_>>(), [2, 3]);
+    }
+
+    #[test]
+    fn recv_timeout() {
+        let (sender, receiver) = unbounded::<u8>();
+        let start = Instant::now();
+        assert_eq!(
+            receiver.recv_timeout(Duration::from_millis(20)),
+            Err(RecvTimeoutError::Timeout)
+        );
+        assert!(start.elapsed() >= Duration::from_millis(20));
+
+        let delayed = thread::spawn(move || {
+            thread::sleep(Duration::from_millis(10));
+            sender.send(9).unwrap();
+        });
+        assert_eq!(receiver.recv_timeout(Duration::from_secs(5)), Ok(9));
+        delayed.join().unwrap();
+        assert_eq!(
+            receiver.recv_timeout(Duration::from_secs(5)),
+            Err(RecvTimeoutError::Disconnected)
+        );
+    }
+
+    #[test]
+    fn disconnect_detection() {
+        let (sender, receiver) = bounded(1);
+        let other = receiver.clone();
+        drop(receiver);
+        sender.send(1).unwrap();
+        drop(other);
+        assert_eq!(sender.send(2), Err(SendError(2)));
+        assert_eq!(sender.try_send(3), Err(TrySendError::Disconnected(3)));
+
+        // 阻塞中的发送方在接收方全部关闭时返回错误
+        let (sender, receiver) = bounded(1);
+        sender.send(0).unwrap();
+        let blocked = thread::spawn(move || sender.send(1));
+        thread::sleep(Duration::from_millis(10));
+        drop(receiver);
+        assert_eq!(blocked.join().unwrap(), Err(SendError(1)));
+    }
+
+    /// 多个接收方 每条消息恰好被收到一次
+    #[test]
+    fn multiple_consumers() {
+        let (sender, receiver) = bounded(8);
+        let consumers: Vec<_> = (0..4)
+            .map(|_| {
+                let receiver = receiver.clone();
+                thread::spawn(move || receiver.iter().collect::<Vec<u32>>())
+            })
+            .collect();
+        drop(receiver);
+        for i in 0..1_000 {
+            sender.send(i).unwrap();
+        }
+        drop(sender);
+
+        let mut all: Vec<u32> = consumers
+            .into_iter()
+            .flat_map(|c| c.join().unwrap())
+            .collect();
+        all.sort();
+        assert_eq!(all, (0..1_000).collect::<Vec<_>>());
+    }
+
+    #[test]
+    fn select_over_receivers() {
+        let (s1, r1) = unbounded();
+        let (s2, r2) = unbounded();
+        s2.send("b").unwrap();
+        assert_eq!(select(&[&r1, &r2]), Ok((1, "b")));
+
+        // 同时就绪时靠前的优先
+        s1.send("a").unwrap();
+        s2.send("c").unwrap();
+        assert_eq!(select(&[&r1, &r2]), Ok((0, "a")));
+        assert_eq!(select(&[&r1, &r2]), Ok((1, "c")));
+
+        // 阻塞等待其它线程发送
+        let sender = thread::spawn(move || {
+            thread::sleep(Duration::from_millis(10));
+            s1.send("late").unwrap();
+        });
+        assert_eq!(select(&[&r1, &r2]), Ok((0, "late")));
+        sender.join().unwrap();
+
+        // r1 已断开 仍然可以等待 r2
+        let closer = thread::spawn(move || {
+            thread::sleep(Duration::from_millis(10));
+            drop(s2);
+        });
+        assert_eq!(select(&[&r1, &r2]), Err(RecvError));
+        closer.join().unwrap();
+        assert!(r1.shared.lock().watchers.is_empty());
+    }
+
+    /// 与 std::sync::mpsc 对比：相同的负载下收到的消息完全一致 且每条消息恰好收到一次
+    #[test]
+    fn stress_against_std_mpsc() {
+        const PRODUCERS: u64 = 4;
+        const MESSAGES: u64 = 10_000;
+
+        fn produce(send: impl Fn(u64) + Send + Clone + 'static) -> Vec<thread::JoinHandle<()>> {
+            (0..PRODUCERS)
+                .map(|p| {
+                    let send = send.clone();
+                    thread::spawn(move || (0..MESSAGES).for_each(|i| send(p * MESSAGES + i)))
+                })
+                .collect()
+        }
+
+        let (sender, receiver) = mpsc::sync_channel(64);
+        let producers = produce(move |v| sender.send(v).unwrap());
+        let mut expected: Vec<u64> = receiver.iter().collect();
+        producers.into_iter().for_each(|p| p.join().unwrap());
+
+        let (sender, receiver) = bounded(64);
+        let producers = produce(move |v| sender.send(v).unwrap());
+        // 我们的通道可以有多个接收方
+        let consumers: Vec<_> = (0..4)
+            .map(|_| {
+                let receiver = receiver.clone();
+                thread::spawn(move || receiver.iter().collect::<Vec<_>>())
+            })
+            .collect();
+        drop(receiver);
+        producers.into_iter().for_each(|p| p.join().unwrap());
+        let mut actual: Vec<u64> = consumers
+            .into_iter()
+            .flat_map(|c| c.join().unwrap())
+            .collect();
+
+        expected.sort();
+        actual.sort();
+        let all: Vec<u64> = (0..PRODUCERS * MESSAGES).collect();
+        assert_eq!(expected, all);
+        assert_eq!(actual, all);
+    }
+}
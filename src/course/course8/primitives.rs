@@ -0,0 +1,466 @@
+//! 并发原语
+//! - [`LruCache`]：RwLock 保护的 LRU 缓存 读操作可以并行
+//! - [`SpinLock`]：用 AtomicBool 实现的自旋锁 演示 Acquire / Release
+//! - [`Counter`] / [`Published`]：原子计数器和不同的内存顺序（Ordering）
+//! - [`map_reduce`]：用 thread::scope 并行处理借用的切片
+//! - [`simulate_phases`]：用 Barrier 让多个线程按阶段同步推进
+//!
+//! 内存顺序简述
+//! - Relaxed：只保证这个变量本身的操作是原子的 适合单纯的计数
+//! - Release（写）/ Acquire（读）：成对使用 Acquire 读到 Release 写入的值后 写入之前的所有操作都对读方可见
+//! - SeqCst：在 Acquire / Release 的基础上 所有 SeqCst 操作存在一个全局一致的顺序
+
+use std::cell::UnsafeCell;
+use std::collections::HashMap;
+use std::hash::Hash;
+use std::ops::{Deref, DerefMut};
+use std::sync::atomic::{AtomicBool, AtomicU64, AtomicUsize, Ordering};
+use std::sync::{Barrier, Mutex, RwLock};
+use std::thread;
+
+struct CacheEntry<V> {
+    value: V,
+    /// 最近一次访问的逻辑时间 用原子变量记录 读锁下也能更新
+    last_used: AtomicU64,
+}
+
+/// 线程安全的 LRU 缓存
+/// 命中时只需要读锁（更新访问时间是原子操作） 插入和淘汰才需要写锁
+/// 淘汰时线性扫描找最久未使用的项 容量较小时足够简单高效
+pub struct LruCache<K, V> {
+    capacity: usize,
+    entries: RwLock<HashMap<K, CacheEntry<V>>>,
+    clock: AtomicU64,
+    hits: AtomicUsize,
+    misses: AtomicUsize,
+}
+
+impl<K: Hash + Eq + Clone, V: Clone> LruCache<K, V> {
+    /// # Panics
+    ///
+    /// capacity 为 0 时 panic
+    pub fn new(capacity: usize) -> Self {
+        assert!(capacity > 0, "capacity must be positive");
+        LruCache {
+            capacity,
+            entries: RwLock::new(HashMap::with_capacity(capacity)),
+            clock: AtomicU64::new(0),
+            hits: AtomicUsize::new(0),
+            misses: AtomicUsize::new(0),
+        }
+    }
+
+    fn tick(&self) -> u64 {
+        self.clock.fetch_add(1, Ordering::Relaxed)
+    }
+
+    pub fn get(&self, key: &K) -> Option<V> {
+        let entries = self.entries.read().unwrap();
+        match entries.get(key) {
+            Some(entry) => {
+                entry.last_used.store(self.tick(), Ordering::Relaxed);
+                self.hits.fetch_add(1, Ordering::Relaxed);
+                Some(entry.value.clone())
+            }
+            None => {
+                self.misses.fetch_add(1, Ordering::Relaxed);
+                None
+            }
+        }
+    }
+
+    /// 插入或更新 缓存已满时淘汰最久未使用的项并返回它
+    pub fn insert(&self, key: K, value: V) -> Option<(K, V)> {
+        let mut entries = self.entries.write().unwrap();
+        let entry = CacheEntry {
+            value,
+            last_used: AtomicU64::new(self.tick()),
+        };
+        if entries.contains_key(&key) || entries.len() < self.capacity {
+            entries.insert(key, entry);
+            return None;
+        }
+        let oldest = entries
+            .iter()
+            .min_by_key(|(_, entry)| entry.last_used.load(Ordering::Relaxed))
+            .map(|(key, _)| key.clone())
+            .expect("cache is full so not empty");
+        let evicted = entries.remove(&oldest).map(|entry| (oldest, entry.value));
+        entries.insert(key, entry);
+        evicted
+    }
+
+    /// 未命中时调用 load 计算并缓存
+    /// 为了不在计算期间持有锁 多个线程可能同时计算同一个 key
+    pub fn get_or_insert_with(&self, key: K, load: impl FnOnce() -> V) -> V {
+        if let Some(value) = self.get(&key) {
+            return value;
+        }
+        let value = load();
+        self.insert(key, value.clone());
+        value
+    }
+
+    pub fn len(&self) -> usize {
+        self.entries.read().unwrap().len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// (命中次数, 未命中次数)
+    pub fn stats(&self) -> (usize, usize) {
+        (
+            self.hits.load(Ordering::Relaxed),
+            self.misses.load(Ordering::Relaxed),
+        )
+    }
+}
+
+/// 自旋锁 等待时不休眠而是循环检查 适合临界区极短的场景
+pub struct SpinLock<T> {
+    locked: AtomicBool,
+    value: UnsafeCell<T>,
+}
+
+// SAFETY: 同一时刻只有持有锁的线程能访问 value 所以只要求 T: Send（与 Mutex 相同）
+unsafe impl<T: Send> Sync for SpinLock<T> {}
+
+impl<T> SpinLock<T> {
+    pub const fn new(value: T) -> Self {
+        SpinLock {
+            locked: AtomicBool::new(false),
+            value: UnsafeCell::new(value),
+        }
+    }
+
+    pub fn lock(&self) -> SpinGuard<'_, T> {
+        // Acquire：拿到锁之后 能看到上一个持有者在 Release 之前做的所有修改
+        while self
+            .locked
+            .compare_exchange_weak(false, true, Ordering::Acquire, Ordering::Relaxed)
+            .is_err()
+        {
+            std::hint::spin_loop();
+        }
+        SpinGuard { lock: self }
+    }
+
+    pub fn into_inner(self) -> T {
+        self.value.into_inner()
+    }
+}
+
+pub struct SpinGuard<'a, T> {
+    lock: &'a SpinLock<T>,
+}
+
+impl<T> Deref for SpinGuard<'_, T> {
+    type Target = T;
+
+    fn deref(&self) -> &T {
+        // SAFETY: 守卫存在期间当前线程独占锁
+        unsafe { &*self.lock.value.get() }
+    }
+}
+
+impl<T> DerefMut for SpinGuard<'_, T> {
+    fn deref_mut(&mut self) -> &mut T {
+        // SAFETY: 同上
+        unsafe { &mut *self.lock.value.get() }
+    }
+}
+
+impl<T> Drop for SpinGuard<'_, T> {
+    fn drop(&mut self) {
+        // Release：把临界区内的修改发布给下一个 Acquire 的线程
+        self.lock.locked.store(false, Ordering::Release);
+    }
+}
+
+/// 原子计数器
+#[derive(Default)]
+pub struct Counter {
+    value: AtomicUsize,
+}
+
+impl Counter {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// 计数本身不用来同步其它数据 Relaxed 就够了 返回增加前的值
+    pub fn increment(&self) -> usize {
+        self.value.fetch_add(1, Ordering::Relaxed)
+    }
+
+    pub fn get(&self) -> usize {
+        self.value.load(Ordering::Relaxed)
+    }
+
+    /// 记录最大值 compare_exchange 失败时用最新值重试
+    pub fn fetch_max(&self, candidate: usize) -> usize {
+        let mut current = self.value.load(Ordering::Relaxed);
+        while candidate > current {
+            match self.value.compare_exchange_weak(
+                current,
+                candidate,
+                Ordering::Relaxed,
+                Ordering::Relaxed,
+            ) {
+                Ok(_) => break,
+                Err(actual) => current = actual,
+            }
+        }
+        current
+    }
+}
+
+/// 一次性发布的数据 演示 Release / Acquire 配对
+/// 数据本身用 Relaxed 写入 由 ready 标记的 Release / Acquire 保证读方能看到它
+#[derive(Default)]
+pub struct Published {
+    data: AtomicU64,
+    ready: AtomicBool,
+}
+
+impl Published {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn publish(&self, value: u64) {
+        self.data.store(value, Ordering::Relaxed);
+        // 如果这里用 Relaxed 读方可能先看到 ready 再看到旧的 data
+        self.ready.store(true, Ordering::Release);
+    }
+
+    pub fn try_read(&self) -> Option<u64> {
+        if self.ready.load(Ordering::Acquire) {
+            Some(self.data.load(Ordering::Relaxed))
+        } else {
+            None
+        }
+    }
+}
+
+/// 把 data 分成 threads 份并行 map 和局部 reduce 最后按顺序合并
+/// 因为按顺序合并 reduce 只需要满足结合律 不需要交换律
+pub fn map_reduce<T, R, M, F>(data: &[T], threads: usize, identity: R, map: M, reduce: F) -> R
+where
+    T: Sync,
+    R: Send + Clone,
+    M: Fn(&T) -> R + Sync,
+    F: Fn(R, R) -> R + Sync,
+{
+    let chunk_size = data.len().div_ceil(threads.max(1)).max(1);
+    // thread::scope 保证所有线程在返回前结束 所以可以直接借用 data / map / reduce
+    thread::scope(|s| {
+        let handles: Vec<_> = data
+            .chunks(chunk_size)
+            .map(|chunk| {
+                let (identity, map, reduce) = (identity.clone(), &map, &reduce);
+                s.spawn(move || chunk.iter().map(map).fold(identity, reduce))
+            })
+            .collect();
+        handles
+            .into_iter()
+            .map(|handle| handle.join().unwrap())
+            .fold(identity.clone(), &reduce)
+    })
+}
+
+/// 阶段记录
+#[derive(Debug, Clone, PartialEq)]
+pub enum PhaseEvent {
+    /// (阶段, 线程)
+    Work(usize, usize),
+    /// 阶段结束 由 Barrier 选出的 leader 记录
+    Done(usize),
+}
+
+/// 多个线程按阶段推进 每个阶段所有线程都完成后才能进入下一阶段
+pub fn simulate_phases(workers: usize, phases: usize) -> Vec<PhaseEvent> {
+    let barrier = Barrier::new(workers);
+    let log = Mutex::new(Vec::new());
+    thread::scope(|s| {
+        for id in 0..workers {
+            let (barrier, log) = (&barrier, &log);
+            s.spawn(move || {
+                for phase in 0..phases {
+                    log.lock().unwrap().push(PhaseEvent::Work(phase, id));
+                    // 所有线程都到达后才继续 恰好有一个线程是 leader
+                    if barrier.wait().is_leader() {
+                        log.lock().unwrap().push(PhaseEvent::Done(phase));
+                    }
+                    // 再同步一次 确保 leader 的记录在下一阶段的工作之前
+                    barrier.wait();
+                }
+            });
+        }
+    });
+    log.into_inner().unwrap()
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use std::sync::Arc;
+
+    #[test]
+    fn lru_evicts_least_recently_used() {
+        let cache = LruCache::new(2);
+        assert_eq!(cache.insert("a", 1), None);
+        assert_eq!(cache.insert("b", 2), None);
+        // 访问 a 后 b 成为最久未使用的
+        assert_eq!(cache.get(&"a"), Some(1));
+        assert_eq!(cache.insert("c", 3), Some(("b", 2)));
+        assert_eq!(cache.get(&"b"), None);
+        // 更新已有的 key 不淘汰
+        assert_eq!(cache.insert("a", 10), None);
+        assert_eq!(cache.len(), 2);
+        assert_eq!(cache.stats(), (1, 1));
+    }
+
+    #[test]
+    fn lru_concurrent_access() {
+        let cache = LruCache::new(16);
+        let loads = AtomicUsize::new(0);
+        thread::scope(|s| {
+            for _ in 0..8 {
+                s.spawn(|| {
+                    for i in 0..200u64 {
+                        let key = i % 16;
+                        let value = cache.get_or_insert_with(key, || {
+                            loads.fetch_add(1, Ordering::Relaxed);
+                            key * key
+                        });
+                        assert_eq!(value, key * key);
+                    }
+                });
+            }
+        });
+        assert_eq!(cache.len(), 16);
+        let (hits, misses) = cache.stats();
+        assert_eq!(hits + misses, 8 * 200);
+        // 每个 key 至少加载一次 并发时可能重复加载 但不会超过未命中次数
+        let loads = loads.load(Ordering::Relaxed);
+        assert!((16..=misses).contains(&loads));
+    }
+
+    #[test]
+    fn spin_lock_mutual_exclusion() {
+        let lock = SpinLock::new(Vec::new());
+        thread::scope(|s| {
+            for id in 0..4 {
+                let lock = &lock;
+                s.spawn(move || {
+                    for i in 0..1_000 {
+                        lock.lock().push((id, i));
+                    }
+                });
+            }
+        });
+        let items = lock.into_inner();
+        assert_eq!(items.len(), 4_000);
+        // 每个线程自己的写入保持顺序
+        for id in 0..4 {
+            let own: Vec<_> = items
+                .iter()
+                .filter(|(t, _)| *t == id)
+                .map(|(_, i)| *i)
+                .collect();
+            assert_eq!(own, (0..1_000).collect::<Vec<_>>());
+        }
+    }
+
+    #[test]
+    fn counter_is_exact() {
+        let counter = Arc::new(Counter::new());
+        let handles: Vec<_> = (0..8)
+            .map(|_| {
+                let counter = Arc::clone(&counter);
+                thread::spawn(move || {
+                    for _ in 0..10_000 {
+                        counter.increment();
+                    }
+                })
+            })
+            .collect();
+        for handle in handles {
+            handle.join().unwrap();
+        }
+        // Relaxed 不影响计数的准确性
+        assert_eq!(counter.get(), 80_000);
+
+        let max = Counter::new();
+        thread::scope(|s| {
+            for t in 0..8 {
+                let max = &max;
+                s.spawn(move || max.fetch_max(t * 10));
+            }
+        });
+        assert_eq!(max.get(), 70);
+    }
+
+    /// 读方一旦看到 ready 就一定能看到发布的数据
+    #[test]
+    fn release_acquire_publication() {
+        for round in 0..100 {
+            let published = Published::new();
+            thread::scope(|s| {
+                s.spawn(|| published.publish(round));
+                s.spawn(|| loop {
+                    if let Some(value) = published.try_read() {
+                        assert_eq!(value, round);
+                        break;
+                    }
+                    std::hint::spin_loop();
+                });
+            });
+        }
+    }
+
+    #[test]
+    fn map_reduce_matches_sequential() {
+        let data: Vec<u64> = (1..=10_000).collect();
+        let sum = map_reduce(&data, 8, 0, |x| x * x, |a, b| a + b);
+        assert_eq!(sum, data.iter().map(|x| x * x).sum::<u64>());
+
+        // 字符串拼接不满足交换律 结果仍然保持原有顺序
+        let words = ["a", "b", "c", "d", "e", "f", "g"];
+        let joined = map_reduce(
+            &words,
+            3,
+            String::new(),
+            |w| w.to_uppercase(),
+            |a, b| a + &b,
+        );
+        assert_eq!(joined, "ABCDEFG");
+
+        assert_eq!(map_reduce(&[] as &[u8], 4, 0, |&x| x, |a, b| a + b), 0);
+        assert_eq!(map_reduce(&[1, 2, 3], 0, 0, |&x| x, |a, b| a + b), 6);
+    }
+
+    #[test]
+    fn barrier_phases_do_not_overlap() {
+        let (workers, phases) = (4, 5);
+        let log = simulate_phases(workers, phases);
+        assert_eq!(log.len(), phases * (workers + 1));
+
+        // 每个阶段是一段连续的记录：所有 Work 之后紧跟一个 Done
+        for (phase, chunk) in log.chunks(workers + 1).enumerate() {
+            let mut ids: Vec<usize> = chunk[..workers]
+                .iter()
+                .map(|event| match event {
+                    PhaseEvent::Work(p, id) if *p == phase => *id,
+                    other => panic!("unexpected {other:?} in phase {phase}"),
+                })
+                .collect();
+            ids.sort();
+            assert_eq!(ids, (0..workers).collect::<Vec<_>>());
+            assert_eq!(chunk[workers], PhaseEvent::Done(phase));
+        }
+    }
+}
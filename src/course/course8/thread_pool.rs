@@ -0,0 +1,436 @@
+//! 线程池
+//! 固定数量的工作线程从同一个任务队列中取任务执行
+//! - 任务队列：mpsc 通道 接收端用 Arc<Mutex<_>> 在工作线程之间共享
+//! - 优雅关闭：Drop 时先关闭通道 工作线程把队列中剩余的任务执行完后退出 再逐个 join
+//! - panic 恢复：每个任务都在 catch_unwind 中执行 任务 panic 不会杀死工作线程
+//! - [`JoinHandle`]：通过一次性通道取回任务的返回值
+//! - [`ThreadPool::scope`]：类似 `std::thread::scope` 任务可以借用栈上的数据
+
+use std::any::Any;
+use std::fmt;
+use std::marker::PhantomData;
+use std::mem;
+use std::panic::{self, AssertUnwindSafe};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{mpsc, Arc, Condvar, Mutex};
+use std::thread;
+
+type Job = Box<dyn FnOnce() + Send + 'static>;
+
+/// 从 panic 的载荷中取出信息
+fn panic_message(payload: &(dyn Any + Send)) -> String {
+    if let Some(message) = payload.downcast_ref::<&str>() {
+        message.to_string()
+    } else if let Some(message) = payload.downcast_ref::<String>() {
+        message.clone()
+    } else {
+        "<non-string panic payload>".to_string()
+    }
+}
+
+/// 任务没有正常返回
+#[derive(Debug, PartialEq)]
+pub enum JoinError {
+    /// 任务 panic 了 附带 panic 信息
+    Panicked(String),
+    /// 任务还没执行就被丢弃了
+    Canceled,
+}
+
+impl fmt::Display for JoinError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            JoinError::Panicked(message) => write!(f, "job panicked: {message}"),
+            JoinError::Canceled => write!(f, "job canceled"),
+        }
+    }
+}
+
+impl std::error::Error for JoinError {}
+
+/// 等待任务结束并取回结果
+pub struct JoinHandle<T> {
+    receiver: mpsc::Receiver<thread::Result<T>>,
+}
+
+impl<T> JoinHandle<T> {
+    pub fn join(self) -> Result<T, JoinError> {
+        match self.receiver.recv() {
+            Ok(Ok(value)) => Ok(value),
+            Ok(Err(payload)) => Err(JoinError::Panicked(panic_message(payload.as_ref()))),
+            // 发送端随任务一起被丢弃 说明任务没有执行
+            Err(_) => Err(JoinError::Canceled),
+        }
+    }
+}
+
+struct Worker {
+    id: usize,
+    handle: Option<thread::JoinHandle<()>>,
+}
+
+impl Worker {
+    fn new(
+        id: usize,
+        receiver: Arc<Mutex<mpsc::Receiver<Job>>>,
+        panicked: Arc<AtomicUsize>,
+    ) -> Worker {
+        let handle = thread::Builder::new()
+            .name(format!("pool-worker-{id}"))
+            .spawn(move || loop {
+                // 锁在这条语句结束时就释放了 执行任务时不持有锁
+                let job = receiver.lock().unwrap().recv();
+                match job {
+                    Ok(job) => {
+                        if panic::catch_unwind(AssertUnwindSafe(job)).is_err() {
+                            panicked.fetch_add(1, Ordering::Relaxed);
+                        }
+                    }
+                    // 发送端已关闭且队列已空 退出
+                    Err(_) => break,
+                }
+            })
+            .expect("failed to spawn worker thread");
+        Worker {
+            id,
+            handle: Some(handle),
+        }
+    }
+}
+
+/// 固定大小的线程池
+pub struct ThreadPool {
+    workers: Vec<Worker>,
+    sender: Option<mpsc::Sender<Job>>,
+    panicked: Arc<AtomicUsize>,
+}
+
+impl ThreadPool {
+    /// 创建有 size 个工作线程的线程池
+    ///
+    /// # Panics
+    ///
+    /// size 为 0 时 panic
+    pub fn new(size: usize) -> ThreadPool {
+        assert!(size > 0, "thread pool size must be positive");
+        let (sender, receiver) = mpsc::channel();
+        let receiver = Arc::new(Mutex::new(receiver));
+        let panicked = Arc::new(AtomicUsize::new(0));
+        let workers = (0..size)
+            .map(|id| Worker::new(id, Arc::clone(&receiver), Arc::clone(&panicked)))
+            .collect();
+        ThreadPool {
+            workers,
+            sender: Some(sender),
+            panicked,
+        }
+    }
+
+    pub fn size(&self) -> usize {
+        self.workers.len()
+    }
+
+    /// 目前为止 panic 的任务数 包括 execute、spawn 和 scope 中提交的任务
+    pub fn panicked_jobs(&self) -> usize {
+        self.panicked.load(Ordering::Relaxed)
+    }
+
+    fn send(&self, job: Job) {
+        self.sender
+            .as_ref()
+            .expect("sender only taken in drop")
+            .send(job)
+            .expect("workers only exit after sender is dropped");
+    }
+
+    /// 提交任务 不关心结果
+    pub fn execute<F>(&self, f: F)
+    where
+        F: FnOnce() + Send + 'static,
+    {
+        self.send(Box::new(f));
+    }
+
+    /// 提交任务 通过返回的 JoinHandle 取回结果
+    pub fn spawn<F, T>(&self, f: F) -> JoinHandle<T>
+    where
+        F: FnOnce() -> T + Send + 'static,
+        T: Send + 'static,
+    {
+        let (sender, receiver) = mpsc::channel();
+        self.execute(move || {
+            // 在这里捕获 panic 以便通过 JoinHandle 传回 然后继续向外传播 让线程池计数
+            let result = panic::catch_unwind(AssertUnwindSafe(f));
+            let resume = match result {
+                Ok(value) => {
+                    let _ = sender.send(Ok(value));
+                    None
+                }
+                Err(payload) => {
+                    let message = panic_message(payload.as_ref());
+                    let _ = sender.send(Err(payload));
+                    Some(message)
+                }
+            };
+            if let Some(message) = resume {
+                panic::resume_unwind(Box::new(message));
+            }
+        });
+        JoinHandle { receiver }
+    }
+
+    /// 作用域任务 任务可以借用 scope 外的数据
+    /// 与 `std::thread::scope` 相同 返回前会等待作用域内提交的所有任务结束
+    ///
+    /// 作用域内有任务 panic 并且没有通过 join 取回时 这里也会 panic
+    ///
+    /// 注意：不要在本线程池的工作线程中调用 所有工作线程都在等待时会死锁
+    pub fn scope<'env, F, R>(&self, f: F) -> R
+    where
+        F: for<'scope> FnOnce(&'scope Scope<'scope, 'env>) -> R,
+    {
+        let scope = Scope {
+            pool: self,
+            state: Arc::new(ScopeState::default()),
+            scope: PhantomData,
+            env: PhantomData,
+        };
+        // 即使 f panic 也必须等任务结束 否则任务可能访问已经释放的栈数据
+        let result = panic::catch_unwind(AssertUnwindSafe(|| f(&scope)));
+        scope.state.wait();
+        match result {
+            Err(payload) => panic::resume_unwind(payload),
+            Ok(_) if scope.state.unjoined_panics.load(Ordering::SeqCst) > 0 => {
+                panic!("a scoped job panicked")
+            }
+            Ok(value) => value,
+        }
+    }
+}
+
+impl Drop for ThreadPool {
+    /// 关闭通道后 工作线程会先执行完队列中剩余的任务再退出
+    fn drop(&mut self) {
+        drop(self.sender.take());
+        for worker in &mut self.workers {
+            if let Some(handle) = worker.handle.take() {
+                // 任务的 panic 都被捕获了 工作线程本身不会 panic
+                handle
+                    .join()
+                    .unwrap_or_else(|_| panic!("worker {} panicked", worker.id));
+            }
+        }
+    }
+}
+
+#[derive(Default)]
+struct ScopeState {
+    pending: Mutex<usize>,
+    done: Condvar,
+    unjoined_panics: AtomicUsize,
+}
+
+impl ScopeState {
+    fn start(&self) {
+        *self.pending.lock().unwrap() += 1;
+    }
+
+    fn finish(&self) {
+        let mut pending = self.pending.lock().unwrap();
+        *pending -= 1;
+        if *pending == 0 {
+            self.done.notify_all();
+        }
+    }
+
+    fn wait(&self) {
+        let mut pending = self.pending.lock().unwrap();
+        while *pending > 0 {
+            pending = self.done.wait(pending).unwrap();
+        }
+    }
+}
+
+/// 作用域 见 [`ThreadPool::scope`]
+/// 'scope 是作用域本身的生命周期 'env 是被借用数据的生命周期
+pub struct Scope<'scope, 'env: 'scope> {
+    pool: &'scope ThreadPool,
+    state: Arc<ScopeState>,
+    // 与 std::thread::Scope 相同 保证两个生命周期都是不变的（invariant）
+    scope: PhantomData<&'scope mut &'scope ()>,
+    env: PhantomData<&'env mut &'env ()>,
+}
+
+impl<'scope> Scope<'scope, '_> {
+    pub fn spawn<F, T>(&'scope self, f: F) -> ScopedJoinHandle<'scope, T>
+    where
+        F: FnOnce() -> T + Send + 'scope,
+        T: Send + 'scope,
+    {
+        let (sender, receiver) = mpsc::channel();
+        let state = Arc::clone(&self.state);
+        let panicked = Arc::clone(&self.pool.panicked);
+        state.start();
+        let job: Box<dyn FnOnce() + Send + 'scope> = Box::new(move || {
+            // panic 在这里就被捕获了 不会传到工作线程 所以需要自己计数
+            let result = panic::catch_unwind(AssertUnwindSafe(f));
+            if result.is_err() {
+                state.unjoined_panics.fetch_add(1, Ordering::SeqCst);
+                panicked.fetch_add(1, Ordering::Relaxed);
+            }
+            let _ = sender.send(result);
+            // 必须在 finish 之前丢弃发送端：通道里可能还存着借用了 'scope 数据的结果
+            drop(sender);
+            state.finish();
+        });
+        // SAFETY: scope 在返回前会等待所有任务调用 finish 而任务在 finish 之后不再访问任何借用的数据
+        // 所以把生命周期延长到 'static 不会产生悬垂引用
+        let job: Job = unsafe { mem::transmute::<Box<dyn FnOnce() + Send + 'scope>, Job>(job) };
+        self.pool.send(job);
+        ScopedJoinHandle {
+            receiver,
+            state: Arc::clone(&self.state),
+            scope: PhantomData,
+        }
+    }
+}
+
+/// 作用域任务的句柄 不能逃出作用域
+pub struct ScopedJoinHandle<'scope, T> {
+    receiver: mpsc::Receiver<thread::Result<T>>,
+    state: Arc<ScopeState>,
+    scope: PhantomData<&'scope ()>,
+}
+
+impl<T> ScopedJoinHandle<'_, T> {
+    pub fn join(self) -> Result<T, JoinError> {
+        match self.receiver.recv() {
+            Ok(Ok(value)) => Ok(value),
+            Ok(Err(payload)) => {
+                // panic 已经被调用方看到了 scope 结束时不再 panic
+                self.state.unjoined_panics.fetch_sub(1, Ordering::SeqCst);
+                Err(JoinError::Panicked(panic_message(payload.as_ref())))
+            }
+            Err(_) => Err(JoinError::Canceled),
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use std::time::Duration;
+
+    #[test]
+    fn join_returns_results_in_order() {
+        let pool = ThreadPool::new(4);
+        assert_eq!(pool.size(), 4);
+        let handles: Vec<_> = (0..100u64).map(|i| pool.spawn(move || i * i)).collect();
+        let results: Vec<u64> = handles.into_iter().map(|h| h.join().unwrap()).collect();
+        assert_eq!(results, (0..100).map(|i| i * i).collect::<Vec<_>>());
+    }
+
+    /// 只有一个工作线程时 任务按提交顺序执行
+    #[test]
+    fn single_worker_is_fifo() {
+        let order = Arc::new(Mutex::new(Vec::new()));
+        {
+            let pool = ThreadPool::new(1);
+            for i in 0..50 {
+                let order = Arc::clone(&order);
+                pool.execute(move || order.lock().unwrap().push(i));
+            }
+        }
+        assert_eq!(*order.lock().unwrap(), (0..50).collect::<Vec<_>>());
+    }
+
+    /// Drop 时队列里积压的任务全部执行完才返回
+    #[test]
+    fn graceful_shutdown_under_load() {
+        let counter = Arc::new(AtomicUsize::new(0));
+        let pool = ThreadPool::new(4);
+        for i in 0..2_000 {
+            let counter = Arc::clone(&counter);
+            pool.execute(move || {
+                if i % 500 == 0 {
+                    thread::sleep(Duration::from_millis(5));
+                }
+                counter.fetch_add(1, Ordering::SeqCst);
+            });
+        }
+        drop(pool);
+        assert_eq!(counter.load(Ordering::SeqCst), 2_000);
+    }
+
+    #[test]
+    fn worker_survives_panic() {
+        let pool = ThreadPool::new(1);
+        let failed = pool.spawn(|| -> u32 { panic!("boom") });
+        let ok = pool.spawn(|| 7);
+        assert_eq!(failed.join(), Err(JoinError::Panicked("boom".into())));
+        assert_eq!(ok.join(), Ok(7));
+
+        pool.execute(|| panic!("fire and forget"));
+        // 唯一的工作线程仍然可用
+        assert_eq!(pool.spawn(|| "alive").join(), Ok("alive"));
+        assert_eq!(pool.panicked_jobs(), 2);
+    }
+
+    #[test]
+    fn scoped_jobs_borrow_stack() {
+        let pool = ThreadPool::new(3);
+        let data: Vec<u64> = (1..=100).collect();
+        let mut doubled = vec![0; data.len()];
+
+        let total = pool.scope(|s| {
+            for (src, dst) in data.chunks(10).zip(doubled.chunks_mut(10)) {
+                s.spawn(move || {
+                    for (d, x) in dst.iter_mut().zip(src) {
+                        *d = x * 2;
+                    }
+                });
+            }
+            let handles: Vec<_> = data
+                .chunks(25)
+                .map(|chunk| s.spawn(move || chunk.iter().sum::<u64>()))
+                .collect();
+            handles.into_iter().map(|h| h.join().unwrap()).sum::<u64>()
+        });
+
+        assert_eq!(total, 5050);
+        // scope 返回时所有任务都已结束 可以直接使用被可变借用的数据
+        assert_eq!(doubled.iter().sum::<u64>(), 10100);
+    }
+
+    #[test]
+    fn scope_waits_for_slow_jobs() {
+        let pool = ThreadPool::new(2);
+        let done = AtomicUsize::new(0);
+        pool.scope(|s| {
+            for _ in 0..4 {
+                s.spawn(|| {
+                    thread::sleep(Duration::from_millis(20));
+                    done.fetch_add(1, Ordering::SeqCst);
+                });
+            }
+        });
+        assert_eq!(done.load(Ordering::SeqCst), 4);
+    }
+
+    #[test]
+    fn scope_panics_on_unjoined_panic() {
+        let pool = ThreadPool::new(2);
+        let joined = pool.scope(|s| s.spawn(|| panic!("seen")).join());
+        assert_eq!(joined, Err(JoinError::Panicked("seen".into())));
+
+        let result = panic::catch_unwind(AssertUnwindSafe(|| {
+            pool.scope(|s| {
+                s.spawn(|| panic!("unseen"));
+            })
+        }));
+        let payload = result.unwrap_err();
+        assert_eq!(panic_message(payload.as_ref()), "a scoped job panicked");
+        // 无论是否 join 作用域任务的 panic 都计入线程池
+        assert_eq!(pool.panicked_jobs(), 2);
+    }
+}
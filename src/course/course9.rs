@@ -1,5 +1,584 @@
-//! todo 添加rust的一些常用trait 
-//! - From & Into see https://rustwiki.org/zh-CN/rust-by-example/conversion/from_into.html
-//! - Iterator
-//! - Option
-//! - Result
\ No newline at end of file
+//! rust的一些常用trait
+//!
+//! 本章学习：
+//! - From & Into & TryFrom：单位换算 see https://rustwiki.org/zh-CN/rust-by-example/conversion/from_into.html
+//! - Iterator：自定义迭代器（斐波那契数列 滑动窗口）和适配器组合
+//! - Option：map / and_then / filter / zip / ok_or 等组合子
+//! - Result：map_err / and_then / ? 以及 collect 成 Result<Vec<_>, _>
+
+use std::collections::VecDeque;
+use std::error::Error;
+use std::fmt;
+use std::io::{self, Write};
+use std::str::FromStr;
+
+/// 米 作为长度的基准单位
+#[derive(Debug, Clone, Copy, PartialEq, PartialOrd)]
+pub struct Meters(pub f64);
+
+#[derive(Debug, Clone, Copy, PartialEq, PartialOrd)]
+pub struct Kilometers(pub f64);
+
+#[derive(Debug, Clone, Copy, PartialEq, PartialOrd)]
+pub struct Feet(pub f64);
+
+const METERS_PER_FOOT: f64 = 0.3048;
+
+/// 实现 From 之后自动获得反方向的 Into：`let m: Meters = km.into();`
+impl From<Kilometers> for Meters {
+    fn from(km: Kilometers) -> Self {
+        Meters(km.0 * 1000.0)
+    }
+}
+
+impl From<Meters> for Kilometers {
+    fn from(m: Meters) -> Self {
+        Kilometers(m.0 / 1000.0)
+    }
+}
+
+impl From<Feet> for Meters {
+    fn from(ft: Feet) -> Self {
+        Meters(ft.0 * METERS_PER_FOOT)
+    }
+}
+
+impl From<Meters> for Feet {
+    fn from(m: Meters) -> Self {
+        Feet(m.0 / METERS_PER_FOOT)
+    }
+}
+
+/// 任何能转换为米的长度都可以相加
+pub fn total_length<L: Into<Meters>>(lengths: impl IntoIterator<Item = L>) -> Meters {
+    Meters(lengths.into_iter().map(|l| l.into().0).sum())
+}
+
+/// 解析长度失败
+#[derive(Debug, PartialEq)]
+pub enum ParseLengthError {
+    UnknownUnit(String),
+    InvalidNumber(String),
+}
+
+impl fmt::Display for ParseLengthError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ParseLengthError::UnknownUnit(unit) => write!(f, "unknown unit {unit:?}"),
+            ParseLengthError::InvalidNumber(number) => write!(f, "invalid number {number:?}"),
+        }
+    }
+}
+
+impl Error for ParseLengthError {}
+
+/// 解析 `12.5km` `3m` `10ft` 这样的字符串 统一换算为米
+impl FromStr for Meters {
+    type Err = ParseLengthError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let s = s.trim();
+        let split = s
+            .find(|c: char| c.is_ascii_alphabetic())
+            .ok_or_else(|| ParseLengthError::UnknownUnit(String::new()))?;
+        let (number, unit) = s.split_at(split);
+        let value: f64 = number
+            .trim()
+            .parse()
+            .map_err(|_| ParseLengthError::InvalidNumber(number.trim().to_string()))?;
+        match unit {
+            "m" => Ok(Meters(value)),
+            "km" => Ok(Kilometers(value).into()),
+            "ft" => Ok(Feet(value).into()),
+            other => Err(ParseLengthError::UnknownUnit(other.to_string())),
+        }
+    }
+}
+
+/// 有了 FromStr 实现 TryFrom<&str> 只需要转发
+impl TryFrom<&str> for Meters {
+    type Error = ParseLengthError;
+
+    fn try_from(s: &str) -> Result<Self, Self::Error> {
+        s.parse()
+    }
+}
+
+/// 摄氏度 不能低于绝对零度 所以只能通过 TryFrom 创建
+#[derive(Debug, Clone, Copy, PartialEq, PartialOrd)]
+pub struct Celsius(f64);
+
+#[derive(Debug, Clone, Copy, PartialEq, PartialOrd)]
+pub struct Fahrenheit(f64);
+
+#[derive(Debug, Clone, Copy, PartialEq, PartialOrd)]
+pub struct Kelvin(f64);
+
+const ABSOLUTE_ZERO_CELSIUS: f64 = -273.15;
+const ABSOLUTE_ZERO_FAHRENHEIT: f64 = -459.67;
+
+#[derive(Debug, PartialEq)]
+pub struct BelowAbsoluteZero(pub f64);
+
+impl fmt::Display for BelowAbsoluteZero {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} is below absolute zero", self.0)
+    }
+}
+
+impl Error for BelowAbsoluteZero {}
+
+impl Celsius {
+    pub fn value(self) -> f64 {
+        self.0
+    }
+}
+
+impl Fahrenheit {
+    pub fn value(self) -> f64 {
+        self.0
+    }
+}
+
+impl Kelvin {
+    pub fn value(self) -> f64 {
+        self.0
+    }
+}
+
+/// 可能失败的转换用 TryFrom 同样会自动获得 TryInto
+impl TryFrom<f64> for Celsius {
+    type Error = BelowAbsoluteZero;
+
+    fn try_from(value: f64) -> Result<Self, Self::Error> {
+        if value < ABSOLUTE_ZERO_CELSIUS || value.is_nan() {
+            Err(BelowAbsoluteZero(value))
+        } else {
+            Ok(Celsius(value))
+        }
+    }
+}
+
+impl TryFrom<f64> for Kelvin {
+    type Error = BelowAbsoluteZero;
+
+    fn try_from(value: f64) -> Result<Self, Self::Error> {
+        if value < 0.0 || value.is_nan() {
+            Err(BelowAbsoluteZero(value))
+        } else {
+            Ok(Kelvin(value))
+        }
+    }
+}
+
+impl TryFrom<f64> for Fahrenheit {
+    type Error = BelowAbsoluteZero;
+
+    fn try_from(value: f64) -> Result<Self, Self::Error> {
+        // 直接与绝对零度比较并保存原值 换算成摄氏度再换算回来会引入浮点误差
+        if value < ABSOLUTE_ZERO_FAHRENHEIT || value.is_nan() {
+            Err(BelowAbsoluteZero(value))
+        } else {
+            Ok(Fahrenheit(value))
+        }
+    }
+}
+
+/// 已经合法的温度之间的换算不会失败 所以用 From
+impl From<Celsius> for Fahrenheit {
+    fn from(c: Celsius) -> Self {
+        Fahrenheit(c.0 * 9.0 / 5.0 + 32.0)
+    }
+}
+
+impl From<Fahrenheit> for Celsius {
+    fn from(f: Fahrenheit) -> Self {
+        Celsius((f.0 - 32.0) * 5.0 / 9.0)
+    }
+}
+
+impl From<Celsius> for Kelvin {
+    fn from(c: Celsius) -> Self {
+        Kelvin(c.0 - ABSOLUTE_ZERO_CELSIUS)
+    }
+}
+
+impl From<Kelvin> for Celsius {
+    fn from(k: Kelvin) -> Self {
+        Celsius(k.0 + ABSOLUTE_ZERO_CELSIUS)
+    }
+}
+
+/// 斐波那契数列 溢出 u64 时结束 所以是一个有限的迭代器
+#[derive(Debug, Clone)]
+pub struct Fibonacci {
+    curr: Option<u64>,
+    next: Option<u64>,
+}
+
+pub fn fibonacci() -> Fibonacci {
+    Fibonacci {
+        curr: Some(0),
+        next: Some(1),
+    }
+}
+
+impl Iterator for Fibonacci {
+    type Item = u64;
+
+    /// 只需要实现 next map / filter / take_while 等适配器都由 Iterator 提供
+    fn next(&mut self) -> Option<u64> {
+        let curr = self.curr?;
+        let next = self.next;
+        self.curr = next;
+        self.next = next.and_then(|next| curr.checked_add(next));
+        Some(curr)
+    }
+}
+
+/// 滑动窗口 与切片的 windows 不同 可以用在任意迭代器上
+#[derive(Debug, Clone)]
+pub struct Windowed<I: Iterator> {
+    iter: I,
+    size: usize,
+    window: VecDeque<I::Item>,
+}
+
+impl<I> Iterator for Windowed<I>
+where
+    I: Iterator,
+    I::Item: Clone,
+{
+    type Item = Vec<I::Item>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.window.len() == self.size {
+            self.window.pop_front();
+        }
+        while self.window.len() < self.size {
+            self.window.push_back(self.iter.next()?);
+        }
+        Some(self.window.iter().cloned().collect())
+    }
+}
+
+/// 扩展特征 为所有迭代器添加 windowed 适配器
+pub trait IteratorExt: Iterator + Sized {
+    /// # Panics
+    ///
+    /// size 为 0 时 panic
+    fn windowed(self, size: usize) -> Windowed<Self> {
+        assert!(size > 0, "window size must be positive");
+        Windowed {
+            iter: self,
+            size,
+            window: VecDeque::with_capacity(size),
+        }
+    }
+}
+
+impl<I: Iterator> IteratorExt for I {}
+
+/// 移动平均
+pub fn moving_average(values: &[f64], size: usize) -> Vec<f64> {
+    values
+        .iter()
+        .copied()
+        .windowed(size)
+        .map(|window| window.iter().sum::<f64>() / size as f64)
+        .collect()
+}
+
+/// 解析坐标对 `3,4` 每一步失败都转换为同一种错误
+#[derive(Debug, PartialEq)]
+pub enum PairError {
+    MissingComma,
+    InvalidNumber(String),
+}
+
+pub fn parse_pair(input: &str) -> Result<(i32, i32), PairError> {
+    let parse = |s: &str| {
+        s.trim()
+            .parse::<i32>()
+            .map_err(|_| PairError::InvalidNumber(s.trim().to_string()))
+    };
+    let (x, y) = input.split_once(',').ok_or(PairError::MissingComma)?;
+    // and_then 把两个可能失败的步骤串起来
+    parse(x).and_then(|x| parse(y).map(|y| (x, y)))
+}
+
+/// 全部解析成功才返回 Ok 遇到第一个错误就停止
+pub fn parse_lengths(inputs: &[&str]) -> Result<Vec<Meters>, ParseLengthError> {
+    inputs.iter().map(|input| input.parse()).collect()
+}
+
+/// 忽略无法解析的项 没有任何合法数字时返回 None
+pub fn average(input: &str) -> Option<f64> {
+    let values: Vec<f64> = input
+        .split_whitespace()
+        .filter_map(|s| s.parse().ok())
+        .collect();
+    (!values.is_empty()).then(|| values.iter().sum::<f64>() / values.len() as f64)
+}
+
+/// Option 上的 ? 与 Result 相同：遇到 None 直接返回
+pub fn middle_initials(full_name: &str) -> Option<String> {
+    let mut parts = full_name.split_whitespace();
+    let _first = parts.next()?;
+    let middle: Vec<&str> = parts.collect();
+    let (_last, middle) = middle.split_last()?;
+    middle
+        .iter()
+        .map(|name| name.chars().next())
+        .collect::<Option<String>>()
+        .filter(|initials| !initials.is_empty())
+}
+
+/// 按名字查找用户的邮箱域名 演示 and_then / map / zip / ok_or
+pub fn email_domain<'a>(users: &[(&str, Option<&'a str>)], name: &str) -> Result<&'a str, String> {
+    users
+        .iter()
+        .find(|(user, _)| *user == name)
+        .ok_or_else(|| format!("no user {name}"))?
+        .1
+        .and_then(|email| email.split_once('@'))
+        .map(|(_, domain)| domain)
+        .filter(|domain| domain.contains('.'))
+        .ok_or_else(|| format!("{name} has no valid email"))
+}
+
+/// 常用特征的简单演示
+pub fn test_all(out: &mut dyn Write) -> io::Result<()> {
+    writeln!(out, "\n\ncourse 9:")?;
+    let m: Meters = Kilometers(1.5).into();
+    let ft = Feet::from(Meters(3.048));
+    writeln!(out, "1.5km = {:?}, 3.048m = {:?}", m, ft)?;
+    writeln!(
+        out,
+        "total length: {:?}",
+        total_length([Meters(1.0), Kilometers(0.001).into(), Feet(10.0).into()])
+    )?;
+    writeln!(out, "parse 2km: {:?}", Meters::try_from("2km"))?;
+    writeln!(out, "parse 2mi: {:?}", "2mi".parse::<Meters>())?;
+
+    let boiling = Celsius::try_from(100.0).map(Fahrenheit::from);
+    let impossible: Result<Kelvin, _> = (-1.0).try_into();
+    writeln!(out, "100C = {:?}, -1K = {:?}", boiling, impossible)?;
+
+    let even_sum: u64 = fibonacci()
+        .take_while(|&n| n < 4_000_000)
+        .filter(|n| n % 2 == 0)
+        .sum();
+    writeln!(
+        out,
+        "fibonacci: {:?}, count until overflow: {}, even sum below 4M: {}",
+        fibonacci().take(10).collect::<Vec<_>>(),
+        fibonacci().count(),
+        even_sum
+    )?;
+    writeln!(
+        out,
+        "windowed: {:?}",
+        (1..=5).windowed(3).collect::<Vec<_>>()
+    )?;
+    writeln!(
+        out,
+        "moving average: {:?}",
+        moving_average(&[1.0, 2.0, 3.0, 4.0], 2)
+    )?;
+
+    writeln!(
+        out,
+        "parse_pair: {:?} {:?}",
+        parse_pair("3, 4"),
+        parse_pair("3 4")
+    )?;
+    writeln!(out, "parse_lengths: {:?}", parse_lengths(&["1m", "1x"]))?;
+    writeln!(
+        out,
+        "average: {:?} {:?}",
+        average("1 2 x 6"),
+        average("x y")
+    )?;
+    writeln!(
+        out,
+        "middle initials: {:?}",
+        middle_initials("John Ronald Reuel Tolkien")
+    )?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn close(a: f64, b: f64) -> bool {
+        (a - b).abs() < 1e-9
+    }
+
+    #[test]
+    fn length_conversions() {
+        let m: Meters = Kilometers(2.5).into();
+        assert_eq!(m, Meters(2500.0));
+        assert_eq!(Kilometers::from(Meters(300.0)), Kilometers(0.3));
+        assert!(close(Meters::from(Feet(1.0)).0, 0.3048));
+        // 来回转换不丢失精度（在误差范围内）
+        let feet = Feet::from(Meters::from(Feet(123.0)));
+        assert!(close(feet.0, 123.0));
+
+        let total = total_length([Meters(1.0), Meters::from(Kilometers(1.0))]);
+        assert_eq!(total, Meters(1001.0));
+        assert_eq!(total_length(Vec::<Meters>::new()), Meters(0.0));
+    }
+
+    #[test]
+    fn parse_length() {
+        assert_eq!("3m".parse(), Ok(Meters(3.0)));
+        assert_eq!(Meters::try_from(" 1.5 km "), Ok(Meters(1500.0)));
+        assert!(close("10ft".parse::<Meters>().unwrap().0, 3.048));
+        assert_eq!(
+            "3mi".parse::<Meters>(),
+            Err(ParseLengthError::UnknownUnit("mi".into()))
+        );
+        assert_eq!(
+            "3".parse::<Meters>(),
+            Err(ParseLengthError::UnknownUnit(String::new()))
+        );
+        assert_eq!(
+            "km".parse::<Meters>(),
+            Err(ParseLengthError::InvalidNumber(String::new()))
+        );
+        assert_eq!(
+            "1.2.3m".parse::<Meters>(),
+            Err(ParseLengthError::InvalidNumber("1.2.3".into()))
+        );
+    }
+
+    #[test]
+    fn temperature_conversions() {
+        let c = Celsius::try_from(100.0).unwrap();
+        assert_eq!(Fahrenheit::from(c), Fahrenheit(212.0));
+        assert!(close(Kelvin::from(c).value(), 373.15));
+        assert!(close(Celsius::from(Fahrenheit(-40.0)).value(), -40.0));
+        assert!(close(
+            Celsius::from(Kelvin::try_from(0.0).unwrap()).value(),
+            -273.15
+        ));
+
+        assert_eq!(Celsius::try_from(-300.0), Err(BelowAbsoluteZero(-300.0)));
+        assert!(Celsius::try_from(f64::NAN).is_err());
+        let k: Result<Kelvin, _> = (-0.5).try_into();
+        assert_eq!(k.unwrap_err().to_string(), "-0.5 is below absolute zero");
+        assert!(Fahrenheit::try_from(-459.0).is_ok());
+        assert!(Fahrenheit::try_from(-460.0).is_err());
+    }
+
+    /// TryFrom 保存的就是输入值 不经过换算
+    #[test]
+    fn temperature_exact_round_trip() {
+        for value in [0.1, -40.0, 98.6, 1e-7, 451.0, -273.15] {
+            assert_eq!(Fahrenheit::try_from(value).unwrap().value(), value);
+            assert_eq!(Celsius::try_from(value).unwrap().value(), value);
+            assert_eq!(Kelvin::try_from(value.abs()).unwrap().value(), value.abs());
+        }
+        assert_eq!(Fahrenheit::try_from(-459.67).unwrap().value(), -459.67);
+        assert!(Fahrenheit::try_from(-459.68).is_err());
+        assert!(Fahrenheit::try_from(f64::NAN).is_err());
+    }
+
+    #[test]
+    fn fibonacci_stream() {
+        assert_eq!(
+            fibonacci().take(10).collect::<Vec<_>>(),
+            [0, 1, 1, 2, 3, 5, 8, 13, 21, 34]
+        );
+        // F(93) 是最后一个不溢出 u64 的斐波那契数
+        assert_eq!(fibonacci().count(), 94);
+        assert_eq!(fibonacci().last(), Some(12_200_160_415_121_876_738));
+
+        let even_sum: u64 = fibonacci()
+            .take_while(|&n| n < 4_000_000)
+            .filter(|n| n % 2 == 0)
+            .sum();
+        assert_eq!(even_sum, 4_613_732);
+        assert_eq!(fibonacci().skip(10).step_by(10).nth(1), Some(6765));
+    }
+
+    #[test]
+    fn windowed_iterator() {
+        assert_eq!(
+            (1..=4).windowed(2).collect::<Vec<_>>(),
+            [vec![1, 2], vec![2, 3], vec![3, 4]]
+        );
+        assert_eq!((1..=2).windowed(3).count(), 0);
+        assert_eq!("abc".chars().windowed(1).count(), 3);
+
+        // 与其它适配器组合：斐波那契相邻两项的比值趋近黄金分割
+        let ratio = fibonacci()
+            .skip(1)
+            .windowed(2)
+            .map(|w| w[1] as f64 / w[0] as f64)
+            .nth(40)
+            .unwrap();
+        assert!((ratio - 1.618_033_988_75).abs() < 1e-9);
+
+        assert_eq!(moving_average(&[1.0, 3.0, 5.0, 7.0], 2), [2.0, 4.0, 6.0]);
+    }
+
+    #[test]
+    #[should_panic(expected = "window size must be positive")]
+    fn windowed_zero_size() {
+        let _ = (0..3).windowed(0);
+    }
+
+    #[test]
+    fn result_pipelines() {
+        assert_eq!(parse_pair("3,4"), Ok((3, 4)));
+        assert_eq!(parse_pair(" -1 , 2 "), Ok((-1, 2)));
+        assert_eq!(parse_pair("3 4"), Err(PairError::MissingComma));
+        assert_eq!(parse_pair("3,x"), Err(PairError::InvalidNumber("x".into())));
+
+        assert_eq!(
+            parse_lengths(&["1m", "1km"]),
+            Ok(vec![Meters(1.0), Meters(1000.0)])
+        );
+        assert_eq!(
+            parse_lengths(&["1m", "1x", "2y"]),
+            Err(ParseLengthError::UnknownUnit("x".into()))
+        );
+    }
+
+    #[test]
+    fn option_pipelines() {
+        assert_eq!(average("1 2 x 6"), Some(3.0));
+        assert_eq!(average(""), None);
+
+        assert_eq!(
+            middle_initials("John Ronald Reuel Tolkien"),
+            Some("RR".into())
+        );
+        assert_eq!(middle_initials("Ada Lovelace"), None);
+        assert_eq!(middle_initials("Plato"), None);
+
+        let users = [
+            ("alice", Some("alice@example.com")),
+            ("bob", None),
+            ("eve", Some("eve@localhost")),
+        ];
+        assert_eq!(email_domain(&users, "alice"), Ok("example.com"));
+        assert_eq!(
+            email_domain(&users, "bob"),
+            Err("bob has no valid email".into())
+        );
+        assert!(email_domain(&users, "eve").is_err());
+        assert_eq!(
+            email_domain(&users, "mallory"),
+            Err("no user mallory".into())
+        );
+
+        // zip：两个值都存在时才组合
+        let width = users.first().map(|(name, _)| name.len());
+        assert_eq!(width.zip(Some(2)), Some((5, 2)));
+        assert_eq!(width.zip(None::<u8>), None);
+    }
+}
@@ -3,6 +3,7 @@
 //! 继续学习 Rust 高级进阶 <https://course.rs/advance/intro.html>
 
 pub mod course1;
+pub mod course10;
 pub mod course2;
 pub mod course3;
 pub mod course4;
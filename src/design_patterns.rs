@@ -1,2 +1,3 @@
+pub mod dispatch;
 pub mod multiple_realization;
 pub mod inversion;
\ No newline at end of file
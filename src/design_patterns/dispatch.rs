@@ -0,0 +1,412 @@
+//! 分发方式的基准测试
+//! inversion 中介绍了 静态分发 和 动态分发 这里用数字说明两者的差异（`hello_world bench`）
+//! - 泛型 / impl Trait：单态化 调用可以内联 但集合只能存放同一种类型
+//! - &dyn Trait / Box<dyn Trait>：胖指针 + 虚函数表 每次调用都是间接跳转
+//! - 枚举分发：类型集合封闭时的折中 可以存放不同类型 调用是一次 match
+//!
+//! 只使用 `std::time::Instant` 计时 结果受机器和编译选项影响很大 请使用 release 模式运行：
+//! `cargo run --release -- bench`
+//!
+//! 测试对象直接复用 multiple_realization 中的职业 为它们实现一个很小的计算特征 [`Work`]
+//! （Profession 的 describe / encode 都要分配 String 直接测它们 测出来的主要是分配的开销）
+//!
+//! 关于二进制大小：每种方式的循环都是单独的 `#[inline(never)]` 函数 运行时从可执行文件的
+//! ELF 符号表中读出这些函数（以及动态分发时通过虚函数表调用的 work）的字节数
+//! - 泛型每种类型单态化出一份循环 release 下 work 被内联进循环
+//! - 动态分发只有一份循环 具体的 work 各自独立存在
+//! - 只支持 64 位小端的 Linux 且可执行文件没有被 strip 否则显示 `-`
+
+use std::collections::HashMap;
+use std::hint::black_box;
+use std::mem::size_of;
+use std::sync::OnceLock;
+use std::time::Instant;
+
+use super::multiple_realization::profession::{Engineer, Teacher};
+
+/// 每种职业做一点不同的计算
+pub trait Work {
+    fn work(&self, input: u64) -> u64;
+}
+
+impl Work for Teacher {
+    fn work(&self, input: u64) -> u64 {
+        input
+            .wrapping_mul(self.subject.len() as u64 | 1)
+            .wrapping_add(1)
+    }
+}
+
+impl Work for Engineer {
+    fn work(&self, input: u64) -> u64 {
+        input.rotate_left(u32::from(self.level) % 64) ^ 0x9e37_79b9
+    }
+}
+
+/// 枚举分发：所有实现在编译期已知
+pub enum Job {
+    Teacher(Teacher),
+    Engineer(Engineer),
+}
+
+impl Work for Job {
+    fn work(&self, input: u64) -> u64 {
+        match self {
+            Job::Teacher(teacher) => teacher.work(input),
+            Job::Engineer(engineer) => engineer.work(input),
+        }
+    }
+}
+
+const SUBJECTS: [&str; 4] = ["math", "music", "art", "history"];
+
+fn teacher(i: usize) -> Teacher {
+    Teacher {
+        subject: SUBJECTS[i % SUBJECTS.len()].to_string(),
+    }
+}
+
+/// 第 i 个元素 两种职业轮流出现
+fn mixed(i: usize) -> Job {
+    if i % 2 == 0 {
+        Job::Teacher(teacher(i))
+    } else {
+        Job::Engineer(Engineer { level: i as u8 })
+    }
+}
+
+fn mixed_box(i: usize) -> Box<dyn Work> {
+    match mixed(i) {
+        Job::Teacher(teacher) => Box::new(teacher),
+        Job::Engineer(engineer) => Box::new(engineer),
+    }
+}
+
+#[inline(never)]
+fn run_generic<T: Work>(items: &[T], rounds: usize) -> u64 {
+    let mut acc = 0;
+    for _ in 0..rounds {
+        for item in items {
+            acc = item.work(acc);
+        }
+    }
+    acc
+}
+
+/// 与泛型完全等价 只是写法不同
+#[inline(never)]
+fn run_impl(items: &[impl Work], rounds: usize) -> u64 {
+    let mut acc = 0;
+    for _ in 0..rounds {
+        for item in items {
+            acc = item.work(acc);
+        }
+    }
+    acc
+}
+
+#[inline(never)]
+fn run_dyn(items: &[&dyn Work], rounds: usize) -> u64 {
+    let mut acc = 0;
+    for _ in 0..rounds {
+        for item in items {
+            acc = item.work(acc);
+        }
+    }
+    acc
+}
+
+#[inline(never)]
+fn run_box(items: &[Box<dyn Work>], rounds: usize) -> u64 {
+    let mut acc = 0;
+    for _ in 0..rounds {
+        for item in items {
+            acc = item.work(acc);
+        }
+    }
+    acc
+}
+
+/// 一行测量结果
+#[derive(Debug)]
+pub struct Row {
+    pub name: &'static str,
+    pub ns_per_op: f64,
+    /// 计算结果 用于校验各种方式算的是同一件事 同时防止被优化掉
+    pub checksum: u64,
+    /// 集合中每个元素的大小（引用 / 指针 / 枚举本身） 是数据布局 不是代码体积
+    pub item_size: usize,
+    /// 循环函数加上通过虚函数表调用的 work 编译后的字节数 读不到符号表时为 None
+    pub code_size: Option<usize>,
+}
+
+/// 64 位 ELF 中函数符号的链接地址到大小的映射
+struct SymbolTable {
+    /// 运行时地址与链接地址的差（位置无关的可执行文件会被加载到随机地址）
+    bias: usize,
+    sizes: HashMap<u64, u64>,
+}
+
+/// 用本函数自己的符号计算加载偏移 legacy 修饰名中包含 `<长度><路径段>`
+const ANCHOR: &[u8] = b"8dispatch12load_symbols17h";
+
+fn read_u16(bytes: &[u8], at: usize) -> Option<u16> {
+    Some(u16::from_le_bytes(bytes.get(at..at + 2)?.try_into().ok()?))
+}
+
+fn read_u32(bytes: &[u8], at: usize) -> Option<u32> {
+    Some(u32::from_le_bytes(bytes.get(at..at + 4)?.try_into().ok()?))
+}
+
+fn read_u64(bytes: &[u8], at: usize) -> Option<u64> {
+    Some(u64::from_le_bytes(bytes.get(at..at + 8)?.try_into().ok()?))
+}
+
+/// 解析当前可执行文件的 `.symtab` 任何一步失败都返回 None
+#[inline(never)]
+fn load_symbols() -> Option<SymbolTable> {
+    if !cfg!(all(
+        target_os = "linux",
+        target_pointer_width = "64",
+        target_endian = "little"
+    )) {
+        return None;
+    }
+    let bytes = std::fs::read(std::env::current_exe().ok()?).ok()?;
+    // 魔数 以及 EI_CLASS == ELFCLASS64
+    if bytes.get(..5)? != b"\x7fELF\x02" {
+        return None;
+    }
+
+    // 节头表
+    let shoff = read_u64(&bytes, 0x28)? as usize;
+    let shentsize = read_u16(&bytes, 0x3a)? as usize;
+    let shnum = read_u16(&bytes, 0x3c)? as usize;
+    let header = |index: usize| shoff + index * shentsize;
+    const SHT_SYMTAB: u32 = 2;
+    let symtab = (0..shnum)
+        .map(header)
+        .find(|&at| read_u32(&bytes, at + 4) == Some(SHT_SYMTAB))?;
+    let offset = read_u64(&bytes, symtab + 0x18)? as usize;
+    let size = read_u64(&bytes, symtab + 0x20)? as usize;
+    // sh_link 指向符号名所在的字符串表
+    let strtab = header(read_u32(&bytes, symtab + 0x28)? as usize);
+    let names = read_u64(&bytes, strtab + 0x18)? as usize;
+
+    const STT_FUNC: u8 = 2;
+    let mut sizes = HashMap::new();
+    let mut anchor = None;
+    for symbol in bytes.get(offset..offset + size)?.chunks_exact(24) {
+        if symbol[4] & 0xf != STT_FUNC {
+            continue;
+        }
+        let value = read_u64(symbol, 8)?;
+        sizes.insert(value, read_u64(symbol, 16)?);
+
+        let name = bytes.get(names + read_u32(symbol, 0)? as usize..)?;
+        let name = &name[..name.iter().position(|&b| b == 0)?];
+        if name.windows(ANCHOR.len()).any(|window| window == ANCHOR) {
+            anchor = Some(value);
+        }
+    }
+    Some(SymbolTable {
+        bias: (load_symbols as fn() -> Option<SymbolTable> as usize).wrapping_sub(anchor? as usize),
+        sizes,
+    })
+}
+
+/// 若干函数编译后的字节数之和 任意一个找不到就返回 None
+fn code_size(functions: &[usize]) -> Option<usize> {
+    static SYMBOLS: OnceLock<Option<SymbolTable>> = OnceLock::new();
+    let table = SYMBOLS.get_or_init(load_symbols).as_ref()?;
+    functions
+        .iter()
+        .map(|&address| {
+            let link = address.wrapping_sub(table.bias) as u64;
+            table.sizes.get(&link).map(|&size| size as usize)
+        })
+        .sum()
+}
+
+/// 预热一轮后计时 返回 (每次调用的纳秒数, 结果)
+fn measure(calls: usize, mut f: impl FnMut() -> u64) -> (f64, u64) {
+    black_box(f());
+    let start = Instant::now();
+    let checksum = black_box(f());
+    let elapsed = start.elapsed();
+    (elapsed.as_nanos() as f64 / calls.max(1) as f64, checksum)
+}
+
+/// 运行所有方式 每种方式调用 `len * rounds` 次 work
+pub fn run(len: usize, rounds: usize) -> Vec<Row> {
+    let calls = len * rounds;
+
+    let teachers: Vec<Teacher> = (0..len).map(teacher).collect();
+    let teacher_refs: Vec<&dyn Work> = teachers.iter().map(|t| t as &dyn Work).collect();
+    let teacher_boxes: Vec<Box<dyn Work>> = (0..len)
+        .map(|i| Box::new(teacher(i)) as Box<dyn Work>)
+        .collect();
+    let teacher_jobs: Vec<Job> = (0..len).map(|i| Job::Teacher(teacher(i))).collect();
+
+    let mixed_jobs: Vec<Job> = (0..len).map(mixed).collect();
+    let mixed_refs: Vec<&dyn Work> = mixed_jobs
+        .iter()
+        .map(|job| match job {
+            Job::Teacher(teacher) => teacher as &dyn Work,
+            Job::Engineer(engineer) => engineer,
+        })
+        .collect();
+    let mixed_boxes: Vec<Box<dyn Work>> = (0..len).map(mixed_box).collect();
+
+    // 函数指针的地址 用于在符号表中查找大小
+    let generic_teacher = run_generic::<Teacher> as fn(&[Teacher], usize) -> u64 as usize;
+    let generic_job = run_generic::<Job> as fn(&[Job], usize) -> u64 as usize;
+    let impl_teacher = run_impl as fn(&[Teacher], usize) -> u64 as usize;
+    let dyn_loop = run_dyn as fn(&[&dyn Work], usize) -> u64 as usize;
+    let box_loop = run_box as fn(&[Box<dyn Work>], usize) -> u64 as usize;
+    let teacher_work = <Teacher as Work>::work as fn(&Teacher, u64) -> u64 as usize;
+    let engineer_work = <Engineer as Work>::work as fn(&Engineer, u64) -> u64 as usize;
+
+    let mut rows = Vec::new();
+    let mut push = |name, item_size, functions: &[usize], (ns_per_op, checksum)| {
+        rows.push(Row {
+            name,
+            ns_per_op,
+            checksum,
+            item_size,
+            code_size: code_size(functions),
+        })
+    };
+
+    // 同一种类型：静态分发的主场
+    push(
+        "generic",
+        size_of::<Teacher>(),
+        &[generic_teacher],
+        measure(calls, || run_generic(black_box(&teachers), rounds)),
+    );
+    push(
+        "impl Trait",
+        size_of::<Teacher>(),
+        &[impl_teacher],
+        measure(calls, || run_impl(black_box(&teachers), rounds)),
+    );
+    push(
+        "&dyn Trait",
+        size_of::<&dyn Work>(),
+        &[dyn_loop, teacher_work],
+        measure(calls, || run_dyn(black_box(&teacher_refs), rounds)),
+    );
+    push(
+        "Box<dyn Trait>",
+        size_of::<Box<dyn Work>>(),
+        &[box_loop, teacher_work],
+        measure(calls, || run_box(black_box(&teacher_boxes), rounds)),
+    );
+    push(
+        "enum",
+        size_of::<Job>(),
+        &[generic_job],
+        measure(calls, || run_generic(black_box(&teacher_jobs), rounds)),
+    );
+
+    // 两种类型混合：泛型无法直接表示 只能用动态分发或枚举
+    push(
+        "&dyn Trait (mixed)",
+        size_of::<&dyn Work>(),
+        &[dyn_loop, teacher_work, engineer_work],
+        measure(calls, || run_dyn(black_box(&mixed_refs), rounds)),
+    );
+    push(
+        "Box<dyn Trait> (mixed)",
+        size_of::<Box<dyn Work>>(),
+        &[box_loop, teacher_work, engineer_work],
+        measure(calls, || run_box(black_box(&mixed_boxes), rounds)),
+    );
+    push(
+        "enum (mixed)",
+        size_of::<Job>(),
+        &[generic_job],
+        measure(calls, || run_generic(black_box(&mixed_jobs), rounds)),
+    );
+
+    rows
+}
+
+/// 以第一行为基准输出对比表格
+pub fn table(rows: &[Row]) -> String {
+    let base = rows
+        .first()
+        .map_or(1.0, |row| row.ns_per_op.max(f64::EPSILON));
+    let mut out = format!(
+        "{:<24}{:>10}{:>10}{:>10}{:>10}\n",
+        "dispatch", "ns/op", "relative", "item(B)", "code(B)"
+    );
+    for row in rows {
+        let code = row
+            .code_size
+            .map_or_else(|| "-".to_string(), |size| size.to_string());
+        out += &format!(
+            "{:<24}{:>10.3}{:>9.2}x{:>10}{:>10}\n",
+            row.name,
+            row.ns_per_op,
+            row.ns_per_op / base,
+            row.item_size,
+            code
+        );
+    }
+    out
+}
+
+/// 运行基准测试并打印表格
+pub fn bench() {
+    if cfg!(debug_assertions) {
+        println!(
+            "注意：当前是 debug 构建 数字没有参考意义 请使用 `cargo run --release -- bench`\n"
+        );
+    }
+    let rows = run(1024, 10_000);
+    print!("{}", table(&rows));
+    println!("\nitem(B)：集合中每个元素的字节数 code(B)：循环函数及其通过虚函数表调用的 work 的机器码字节数");
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    /// 各种分发方式计算的结果必须一致 否则比较没有意义
+    #[test]
+    fn same_checksum() {
+        let rows = run(30, 3);
+        assert_eq!(rows.len(), 8);
+        let (same, mixed) = rows.split_at(5);
+        assert!(same.iter().all(|row| row.checksum == same[0].checksum));
+        assert!(mixed.iter().all(|row| row.checksum == mixed[0].checksum));
+        assert_ne!(same[0].checksum, mixed[0].checksum);
+
+        assert_eq!(size_of::<&dyn Work>(), 2 * size_of::<usize>());
+    }
+
+    /// Linux 上从符号表读到每个函数的大小 混合时动态分发多算一个 work
+    #[test]
+    #[cfg(all(target_os = "linux", target_pointer_width = "64"))]
+    fn code_size_from_symbols() {
+        let rows = run(3, 1);
+        let sizes: Vec<usize> = rows.iter().map(|row| row.code_size.unwrap()).collect();
+        assert!(sizes.iter().all(|&size| size > 0), "{sizes:?}");
+        // 同一个函数 两次读到的大小一致
+        assert_eq!(sizes[4], sizes[7]);
+        assert!(sizes[5] > sizes[2]);
+        assert!(sizes[6] > sizes[3]);
+    }
+
+    #[test]
+    fn table_has_every_row() {
+        let rows = run(3, 1);
+        let table = table(&rows);
+        assert_eq!(table.lines().count(), rows.len() + 1);
+        assert!(table.starts_with("dispatch"));
+        assert!(table.contains("Box<dyn Trait> (mixed)"));
+        assert!(table.lines().nth(1).unwrap().contains("1.00x"));
+    }
+}
@@ -8,17 +8,71 @@
 //!   - 简短语法 `fn foo(arg: impl Trait) {}` 不支持 turbo-fish 语法
 //! - 动态分发（类型擦除的对象指针和虚函数表） 运行时动态生成胖指针 解引用导致开销大
 //!   - 一般跟引用或指针一起使用 `fn foo(arg: &dyn Trait) {}` or `fn foo() -> Box<dyn Trait> {}`
+//! - 两者的实际开销对比见 dispatch 模块（`cargo run --release -- bench`）
 //! 
 //! 这里增加对 泛型 和 关联类型 的说明
 //! - 实现带泛型的特征时 对于同一类特征可以实现多次（所以称之为泛型）
 //! - 实现有关联类型的特征时 仅能存在一个关联类型（因为关联类型与特征相关联）
+//!
+//! 在此基础上逐步扩展出的框架功能见各子模块
+//! - router 按字符串注册和分发处理函数
+//! - extension 按类型注入共享数据
+//! - async_handler 异步处理函数和最简单的执行器
+//! - middleware 中间件
+//! - schedule 类似 Bevy 的系统调度
+//! - event 事件总线
 
 #![allow(dead_code)]
 
+use std::any;
+use std::convert::Infallible;
+use std::fmt;
+use std::io::{self, Write};
+use std::str::FromStr;
+
+pub mod async_handler;
+pub mod event;
+pub mod extension;
+pub mod middleware;
+pub mod router;
+pub mod schedule;
+
+use crate::course::course8::async_runtime::block_on;
+use extension::Extensions;
+
 /// 首先定义一个上下文类型存放所有东西
 pub struct Context {
     pub id: u32,
     pub param: String,
+    /// 按类型存放的其他数据 见 [`extension`]
+    extensions: Extensions,
+}
+
+impl Context {
+    pub fn new(id: u32, param: impl Into<String>) -> Self {
+        Context {
+            id,
+            param: param.into(),
+            extensions: Extensions::new(),
+        }
+    }
+
+    /// 注入一份数据 同一类型只保留一份 返回旧值
+    pub fn insert<T: 'static>(&mut self, value: T) -> Option<T> {
+        self.extensions.insert(value)
+    }
+
+    pub fn get<T: 'static>(&self) -> Option<&T> {
+        self.extensions.get()
+    }
+
+    pub fn get_mut<T: 'static>(&mut self) -> Option<&mut T> {
+        self.extensions.get_mut()
+    }
+
+    pub fn remove<T: 'static>(&mut self) -> Option<T> {
+        self.extensions.remove()
+    }
 }
 
 /// 其次定义一个抽象特征 支持从上下文中进行类型转换  
@@ -28,77 +82,583 @@ pub trait FromContext {
     fn from_context(ctx: &Context) -> Self;
 }
 
-/// 再者定义一个抽象的代理函数  
-/// 泛型 T 允许对于不同类型分别不同实现 起到类似于重载的作用
-/// 需要框架侧多多个 Func 做实现
-trait Handler<T> {
-    fn call(self, ctx: &Context);
+/// 提取器示例：取出上下文中的 id
+pub struct Id(pub u32);
+
+impl FromContext for Id {
+    fn from_context(ctx: &Context) -> Self {
+        Id(ctx.id)
+    }
 }
 
-impl<T: FromContext, F: Fn(T)> Handler<T> for F {
-    fn call(self, ctx: &Context) {
-        self(T::from_context(ctx));
+/// 提取器示例：取出上下文中的 param
+pub struct Param(pub String);
+
+impl FromContext for Param {
+    fn from_context(ctx: &Context) -> Self {
+        Param(ctx.param.clone())
     }
 }
 
-impl<T1, T2, F> Handler<(T1, T2)> for F
+/// 可能失败的提取 失败时返回 Rejection 说明原因
+///
+/// 所有 [`FromContext`] 都自动实现了它（永不失败 Rejection 为 [`Infallible`]）
+/// 框架侧调用处理函数时统一使用这个特征
+pub trait TryFromContext: Sized {
+    type Rejection: fmt::Display;
+    fn try_from_context(ctx: &Context) -> Result<Self, Self::Rejection>;
+}
+
+impl<T: FromContext> TryFromContext for T {
+    type Rejection = Infallible;
+
+    fn try_from_context(ctx: &Context) -> Result<Self, Self::Rejection> {
+        Ok(T::from_context(ctx))
+    }
+}
+
+/// 可选参数：提取失败时得到 None 而不是中断分发
+impl<T: TryFromContext> TryFromContext for Option<T> {
+    type Rejection = Infallible;
+
+    fn try_from_context(ctx: &Context) -> Result<Self, Self::Rejection> {
+        Ok(T::try_from_context(ctx).ok())
+    }
+}
+
+/// 由处理函数自己处理失败原因
+impl<T: TryFromContext> TryFromContext for Result<T, T::Rejection> {
+    type Rejection = Infallible;
+
+    fn try_from_context(ctx: &Context) -> Result<Self, Self::Rejection> {
+        Ok(T::try_from_context(ctx))
+    }
+}
+
+/// 提取器示例：将 param 解析为指定类型 可能失败
+pub struct Parsed<T>(pub T);
+
+/// [`Parsed`] 失败的原因
+#[derive(Debug, Clone, PartialEq)]
+pub enum ParamRejection {
+    Missing,
+    Invalid { input: String, reason: String },
+}
+
+impl fmt::Display for ParamRejection {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ParamRejection::Missing => write!(f, "param is missing"),
+            ParamRejection::Invalid { input, reason } => {
+                write!(f, "invalid param `{input}`: {reason}")
+            }
+        }
+    }
+}
+
+impl<T> TryFromContext for Parsed<T>
 where
-    F: Fn(T1, T2),
-    T1: FromContext,
-    T2: FromContext,
+    T: FromStr,
+    T::Err: fmt::Display,
 {
-    fn call(self, ctx: &Context) {
-        self(T1::from_context(ctx), T2::from_context(ctx));
+    type Rejection = ParamRejection;
+
+    fn try_from_context(ctx: &Context) -> Result<Self, Self::Rejection> {
+        if ctx.param.is_empty() {
+            return Err(ParamRejection::Missing);
+        }
+        ctx.param
+            .parse()
+            .map(Parsed)
+            .map_err(|err: T::Err| ParamRejection::Invalid {
+                input: ctx.param.clone(),
+                reason: err.to_string(),
+            })
+    }
+}
+
+/// 借用提取器 直接从上下文中借出数据 不需要 clone
+///
+/// 生命周期 'a 表示提取结果借用自上下文 只在本次调用期间有效
+/// 所有 [`TryFromContext`] 都自动实现了它（结果不借用上下文）
+pub trait FromContextRef<'a>: Sized {
+    type Rejection: fmt::Display;
+    fn from_context_ref(ctx: &'a Context) -> Result<Self, Self::Rejection>;
+}
+
+impl<'a, T: TryFromContext> FromContextRef<'a> for T {
+    type Rejection = T::Rejection;
+
+    fn from_context_ref(ctx: &'a Context) -> Result<Self, Self::Rejection> {
+        T::try_from_context(ctx)
+    }
+}
+
+/// 借出 param 对比 [`Param`] 少了一次 clone
+impl<'a> FromContextRef<'a> for &'a str {
+    type Rejection = Infallible;
+
+    fn from_context_ref(ctx: &'a Context) -> Result<Self, Self::Rejection> {
+        Ok(&ctx.param)
+    }
+}
+
+/// 直接借出整个上下文
+impl<'a> FromContextRef<'a> for &'a Context {
+    type Rejection = Infallible;
+
+    fn from_context_ref(ctx: &'a Context) -> Result<Self, Self::Rejection> {
+        Ok(ctx)
+    }
+}
+
+/// 处理函数的参数类型 到 任意生命周期下的提取类型 的映射（泛型关联类型 GAT）
+///
+/// 处理函数 `fn(&str)` 实际上是 `for<'a> fn(&'a str)` 能接受任意生命周期
+/// 但 Handler 的泛型参数 T 只能是一个具体类型 无法表达“任意生命周期”
+/// 这里借鉴 Bevy 的 SystemParam：用 `Item<'a>` 把生命周期留给调用时再决定
+pub trait ContextParam {
+    type Item<'a>: FromContextRef<'a>;
+}
+
+impl<T: TryFromContext> ContextParam for T {
+    type Item<'a> = T;
+}
+
+impl ContextParam for &str {
+    type Item<'a> = &'a str;
+}
+
+impl ContextParam for &Context {
+    type Item<'a> = &'a Context;
+}
+
+/// 处理函数的返回值 由框架统一处理
+#[derive(Debug, Clone, PartialEq)]
+pub enum Response {
+    Empty,
+    Text(String),
+}
+
+/// 类似于 Axum 的 IntoResponse 处理函数可以返回任意实现了该特征的类型
+pub trait IntoResponse {
+    fn into_response(self) -> Response;
+}
+
+impl IntoResponse for Response {
+    fn into_response(self) -> Response {
+        self
+    }
+}
+
+impl IntoResponse for () {
+    fn into_response(self) -> Response {
+        Response::Empty
+    }
+}
+
+impl IntoResponse for String {
+    fn into_response(self) -> Response {
+        Response::Text(self)
+    }
+}
+
+impl IntoResponse for &'static str {
+    fn into_response(self) -> Response {
+        Response::Text(self.to_string())
+    }
+}
+
+/// 数字类型统一转为文本
+macro_rules! impl_into_response_for_number {
+    ($($ty:ty),*) => {
+        $(
+            impl IntoResponse for $ty {
+                fn into_response(self) -> Response {
+                    Response::Text(self.to_string())
+                }
+            }
+        )*
+    };
+}
+
+impl_into_response_for_number!(i8, i16, i32, i64, u8, u16, u32, u64, usize, f64, bool);
+
+impl<T: IntoResponse> IntoResponse for Option<T> {
+    fn into_response(self) -> Response {
+        self.map_or(Response::Empty, IntoResponse::into_response)
     }
 }
 
+impl<T: IntoResponse, E: IntoResponse> IntoResponse for Result<T, E> {
+    fn into_response(self) -> Response {
+        match self {
+            Ok(value) => value.into_response(),
+            Err(err) => err.into_response(),
+        }
+    }
+}
+
+/// 分发失败的原因
+#[derive(Debug, Clone, PartialEq)]
+pub enum DispatchError {
+    /// 没有注册对应的处理函数
+    NotFound(String),
+    /// 提取参数失败 记录提取器的类型名以及失败原因
+    Rejected {
+        extractor: &'static str,
+        reason: String,
+    },
+    /// 被中间件拦截 见 [`middleware`]
+    Aborted { layer: &'static str, reason: String },
+}
+
+impl fmt::Display for DispatchError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            DispatchError::NotFound(key) => write!(f, "no handler registered for `{key}`"),
+            DispatchError::Rejected { extractor, reason } => {
+                write!(f, "extractor `{extractor}` rejected: {reason}")
+            }
+            DispatchError::Aborted { layer, reason } => {
+                write!(f, "aborted by `{layer}`: {reason}")
+            }
+        }
+    }
+}
+
+impl std::error::Error for DispatchError {}
+
+/// 提取单个参数 失败时转换为 [`DispatchError::Rejected`]
+fn extract<'a, T: FromContextRef<'a>>(ctx: &'a Context) -> Result<T, DispatchError> {
+    T::from_context_ref(ctx).map_err(|rejection| DispatchError::Rejected {
+        extractor: any::type_name::<T>(),
+        reason: rejection.to_string(),
+    })
+}
+
+/// 再者定义一个抽象的代理函数  
+/// 泛型 T 允许对于不同类型分别不同实现 起到类似于重载的作用
+/// 需要框架侧多多个 Func 做实现
+pub trait Handler<T> {
+    fn call(self, ctx: &Context) -> Result<Response, DispatchError>;
+}
+
+/// 框架侧为不同参数个数的函数分别实现 Handler 这里用宏批量生成
+/// - T 为参数类型组成的元组 用于区分不同的实现（否则会冲突）
+/// - R 为返回值类型 通过 `Fn(..) -> R` 的约束推导出来
+/// - 参数从左到右依次提取 任意一个失败就直接返回 不会调用处理函数
+/// - 两条 Fn 约束：第一条用于推导参数类型 第二条保证函数能接受从当前上下文借出的参数
+macro_rules! impl_handler {
+    ($($ty:ident),*) => {
+        #[allow(non_snake_case)] // 宏里复用类型名作为变量名
+        impl<F, R, $($ty,)*> Handler<($($ty,)*)> for F
+        where
+            F: Fn($($ty),*) -> R,
+            F: for<'a> Fn($(<$ty as ContextParam>::Item<'a>),*) -> R,
+            R: IntoResponse,
+            $($ty: ContextParam,)*
+        {
+            #[allow(unused_variables)] // 零个参数时 ctx 未使用
+            fn call(self, ctx: &Context) -> Result<Response, DispatchError> {
+                // 直接 self(..) 编译器会选中第一条约束 借助这个函数明确使用第二条
+                #[allow(clippy::too_many_arguments)]
+                fn call_inner<R, $($ty,)*>(f: impl Fn($($ty),*) -> R, $($ty: $ty,)*) -> R {
+                    f($($ty),*)
+                }
+
+                $(let $ty = extract::<<$ty as ContextParam>::Item<'_>>(ctx)?;)*
+                Ok(call_inner(self, $($ty),*).into_response())
+            }
+        }
+    };
+}
+
+impl_handler!();
+impl_handler!(T1);
+impl_handler!(T1, T2);
+impl_handler!(T1, T2, T3);
+impl_handler!(T1, T2, T3, T4);
+impl_handler!(T1, T2, T3, T4, T5);
+impl_handler!(T1, T2, T3, T4, T5, T6);
+impl_handler!(T1, T2, T3, T4, T5, T6, T7);
+impl_handler!(T1, T2, T3, T4, T5, T6, T7, T8);
+impl_handler!(T1, T2, T3, T4, T5, T6, T7, T8, T9);
+impl_handler!(T1, T2, T3, T4, T5, T6, T7, T8, T9, T10);
+impl_handler!(T1, T2, T3, T4, T5, T6, T7, T8, T9, T10, T11);
+impl_handler!(T1, T2, T3, T4, T5, T6, T7, T8, T9, T10, T11, T12);
+
 /// 然后定义注册和触发方式
-/// - 注册函数 由使用方将定制化的逻辑注入（这里没举例）
+/// - 注册函数 由使用方将定制化的逻辑注入 见 [`router::Router`]
 /// - 触发函数 由框架侧根据生命周期自动调用
 #[inline]
-fn trigger<T>(ctx: &Context, handler: impl Handler<T>) {
-    handler.call(ctx);
+pub fn dispatch<T>(ctx: &Context, handler: impl Handler<T>) -> Result<Response, DispatchError> {
+    handler.call(ctx)
 }
 
-#[cfg(test)]
-mod test {
-    use super::*;
+/// 类型擦除后的处理函数
+/// 不同的处理函数是不同的类型 需要存进同一个容器时（路由 调度 事件总线）统一包装成它
+pub(super) type BoxedHandler = Box<dyn Fn(&Context) -> Result<Response, DispatchError>>;
 
-    pub struct Id(pub u32);
+/// `Handler::call` 会消耗 self 而容器中的处理函数需要多次调用 所以要求 Clone
+pub(super) fn boxed<T, H>(handler: H) -> BoxedHandler
+where
+    T: 'static,
+    H: Handler<T> + Clone + 'static,
+{
+    Box::new(move |ctx| handler.clone().call(ctx))
+}
 
-    impl FromContext for Id {
-        fn from_context(ctx: &Context) -> Self {
-            Id(ctx.id)
+/// 控制反转的简单演示 使用方只需要声明参数类型 框架负责构造参数并调用
+pub fn test_all(out: &mut dyn Write) -> io::Result<()> {
+    writeln!(out, "\n\ninversion:")?;
+    let mut ctx = Context::new(123, "asd");
+    // 处理函数返回字符串 由调用方决定输出到哪里
+    let responses = [
+        dispatch(&ctx, |Id(id): Id| format!("id is {}", id)),
+        dispatch(&ctx, |Param(param): Param, Id(id): Id| {
+            format!("param is {}, id is {}", param, id)
+        }),
+        dispatch(&ctx, |param: &str| format!("borrowed param is {param}")),
+    ];
+    for response in responses {
+        if let Ok(Response::Text(text)) = response {
+            writeln!(out, "{text}")?;
         }
     }
+    writeln!(
+        out,
+        "parse param as number: {:?}",
+        dispatch(&ctx, |Parsed(n): Parsed<u32>| n)
+    )?;
+
+    let router = router::Router::new()
+        .route("echo", |Param(param): Param| param)
+        .route("double", |Id(id): Id| id * 2);
+    for key in ["echo", "double", "missing"] {
+        writeln!(out, "route {key}: {:?}", router.dispatch(key, &ctx))?;
+    }
+
+    let async_result = block_on(async_handler::dispatch_async(
+        &ctx,
+        |Id(id): Id| async move { id + 1 },
+    ));
+    writeln!(out, "async handler: {:?}", async_result)?;
+
+    let logging = middleware::Logging::new();
+    let pipeline = middleware::Pipeline::new(router)
+        .layer(logging.clone())
+        .layer(middleware::AuthCheck::new("secret"));
+    let _ = pipeline.handle("echo", &mut ctx);
+    ctx.insert(middleware::Token("secret".into()));
+    let _ = pipeline.handle("echo", &mut ctx);
+    for line in logging.lines() {
+        writeln!(out, "middleware log: {line}")?;
+    }
+
+    ctx.insert(String::from("injected state"));
+    if let Ok(Response::Text(text)) = dispatch(&ctx, |state: extension::Res<String>| {
+        format!("state is {}", *state)
+    }) {
+        writeln!(out, "{text}")?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
 
     /// 解构
     fn print_id(Id(id): Id) {
         println!("id is {}", id);
     }
 
-    /// 对于复杂的对象 使用引用 标明生命周期
-    pub struct Param(pub String);
+    fn print_param_id(Param(param): Param, Id(id): Id) {
+        println!("param is {}, id is {}", param, id);
+    }
 
-    impl FromContext for Param {
-        fn from_context(ctx: &Context) -> Self {
-            Param(ctx.param.clone())
+    fn context() -> Context {
+        Context::new(123, "asd")
+    }
+
+    #[test]
+    fn test() {
+        let ctx = context();
+
+        // 演示
+        dispatch(&ctx, print_id).unwrap();
+        dispatch(&ctx, print_param_id).unwrap();
+    }
+
+    #[test]
+    fn handler_returns_response() {
+        let ctx = context();
+
+        assert_eq!(dispatch(&ctx, || {}), Ok(Response::Empty));
+        assert_eq!(
+            dispatch(&ctx, || "static"),
+            Ok(Response::Text("static".into()))
+        );
+        assert_eq!(
+            dispatch(&ctx, |Id(id): Id| id + 1),
+            Ok(Response::Text("124".into()))
+        );
+        assert_eq!(
+            dispatch(&ctx, |Param(p): Param| (!p.is_empty()).then_some(p)),
+            Ok(Response::Text("asd".into()))
+        );
+        assert_eq!(
+            dispatch(&ctx, |Id(id): Id| -> Result<u32, String> {
+                Err(format!("bad id {id}"))
+            }),
+            Ok(Response::Text("bad id 123".into()))
+        );
+    }
+
+    /// 宏生成的实现最多支持 12 个参数
+    #[test]
+    fn twelve_arguments() {
+        #[allow(clippy::too_many_arguments)]
+        fn sum(
+            Id(a): Id,
+            Id(b): Id,
+            Id(c): Id,
+            Id(d): Id,
+            Id(e): Id,
+            Id(f): Id,
+            Id(g): Id,
+            Id(h): Id,
+            Id(i): Id,
+            Id(j): Id,
+            Id(k): Id,
+            Param(l): Param,
+        ) -> String {
+            format!("{}{}", a + b + c + d + e + f + g + h + i + j + k, l)
         }
+
+        assert_eq!(
+            dispatch(&context(), sum),
+            Ok(Response::Text("1353asd".into()))
+        );
     }
 
-    fn print_param_id(Param(param): Param, Id(id): Id) {
-        println!("param is {}, id is {}", param, id);
+    fn context_with_param(param: &str) -> Context {
+        Context::new(1, param)
     }
 
     #[test]
-    fn test() {
-        let ctx = Context {
-            param: "asd".into(),
-            id: 123,
+    fn fallible_extractor() {
+        let double = |Parsed(n): Parsed<i32>| n * 2;
+        assert_eq!(
+            dispatch(&context_with_param("21"), double),
+            Ok(Response::Text("42".into()))
+        );
+
+        let err = dispatch(&context_with_param(""), double).unwrap_err();
+        assert_eq!(
+            err,
+            DispatchError::Rejected {
+                extractor: any::type_name::<Parsed<i32>>(),
+                reason: "param is missing".into(),
+            }
+        );
+
+        let err = dispatch(&context_with_param("abc"), double).unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            format!(
+                "extractor `{}` rejected: invalid param `abc`: invalid digit found in string",
+                any::type_name::<Parsed<i32>>()
+            )
+        );
+    }
+
+    /// 第一个失败的提取器会中断分发 后面的提取器和处理函数都不会执行
+    #[test]
+    fn short_circuit() {
+        use std::cell::Cell;
+
+        thread_local! {
+            static EXTRACTED: Cell<u32> = const { Cell::new(0) };
+        }
+
+        struct Counted;
+
+        impl FromContext for Counted {
+            fn from_context(_: &Context) -> Self {
+                EXTRACTED.with(|count| count.set(count.get() + 1));
+                Counted
+            }
+        }
+
+        let handler = |_: Counted, Parsed(_): Parsed<u8>, _: Counted| -> &'static str {
+            unreachable!("handler must not run")
         };
+        let err = dispatch(&context_with_param("300"), handler).unwrap_err();
+        assert!(
+            matches!(err, DispatchError::Rejected { extractor, .. } if extractor.contains("Parsed<u8>"))
+        );
+        assert_eq!(EXTRACTED.with(Cell::get), 1);
+    }
 
-        // 演示
-        trigger(&ctx, print_id);
-        trigger(&ctx, print_param_id);
+    /// 借用提取器：直接拿到上下文里的引用 不会 clone
+    #[test]
+    fn borrowed_extractors() {
+        let ctx = context();
+
+        fn param_len(param: &str) -> usize {
+            param.len()
+        }
+        assert_eq!(dispatch(&ctx, param_len), Ok(Response::Text("3".into())));
+
+        // 借出的就是上下文里的同一块内存
+        let same_memory = |param: &str, ctx: &Context| std::ptr::eq(param, ctx.param.as_str());
+        assert_eq!(
+            dispatch(&ctx, same_memory),
+            Ok(Response::Text("true".into()))
+        );
+    }
+
+    /// 借用提取器和所有权提取器（包括可能失败的）可以混用
+    #[test]
+    fn mixed_extractors() {
+        fn describe(Id(id): Id, param: &str, Parsed(n): Parsed<u32>, ctx: &Context) -> String {
+            format!("{id}:{param}:{}:{}", n + 1, ctx.id == id)
+        }
+
+        assert_eq!(
+            dispatch(&context_with_param("41"), describe),
+            Ok(Response::Text("1:41:42:true".into()))
+        );
+        assert!(matches!(
+            dispatch(&context_with_param("x"), describe),
+            Err(DispatchError::Rejected { .. })
+        ));
+    }
+
+    #[test]
+    fn optional_and_result_extractors() {
+        let handler = |n: Option<Parsed<u32>>| match n {
+            Some(Parsed(n)) => format!("got {n}"),
+            None => "nothing".to_string(),
+        };
+        assert_eq!(
+            dispatch(&context_with_param("5"), handler),
+            Ok(Response::Text("got 5".into()))
+        );
+        assert_eq!(
+            dispatch(&context_with_param("x"), handler),
+            Ok(Response::Text("nothing".into()))
+        );
+
+        let handler = |n: Result<Parsed<u32>, ParamRejection>| match n {
+            Ok(Parsed(n)) => n.to_string(),
+            Err(rejection) => format!("handled: {rejection}"),
+        };
+        assert_eq!(
+            dispatch(&context_with_param(""), handler),
+            Ok(Response::Text("handled: param is missing".into()))
+        );
     }
 }
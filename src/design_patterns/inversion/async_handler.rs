@@ -0,0 +1,225 @@
+//! 异步处理函数
+//! 与 [`Handler`](super::Handler) 相同的控制反转思路 只是处理函数返回 Future 提取器也可以是异步的
+//!
+//! 标准库只定义了 Future 和 Waker 没有提供执行器 这里使用 course8 异步运行时中最简单的 [`block_on`]
+//! 它直接在当前线程上 poll 不要求 Future 是 Send / 'static 所以处理函数可以借用上下文
+
+use std::any;
+use std::collections::HashMap;
+use std::fmt;
+use std::future::Future;
+
+use crate::course::course8::async_runtime::{block_on, yield_now};
+
+use super::router::Router;
+use super::{Context, DispatchError, Handler, IntoResponse, Response, TryFromContext};
+
+/// 异步提取器
+///
+/// 所有 [`TryFromContext`] 都自动实现了它（立即完成）
+/// 返回的 Future 可以借用上下文 因此不需要 clone
+pub trait FromContextAsync: Sized {
+    type Rejection: fmt::Display;
+    fn from_context_async(ctx: &Context) -> impl Future<Output = Result<Self, Self::Rejection>>;
+}
+
+impl<T: TryFromContext> FromContextAsync for T {
+    type Rejection = T::Rejection;
+
+    fn from_context_async(ctx: &Context) -> impl Future<Output = Result<Self, Self::Rejection>> {
+        std::future::ready(T::try_from_context(ctx))
+    }
+}
+
+/// 异步提取器示例：用 param 作为键 在上下文中的 [`Directory`] 里查询
+/// 模拟需要等待 IO 的提取（如查询数据库）
+pub struct Lookup(pub String);
+
+/// 供 [`Lookup`] 查询的数据 需要事先注入上下文
+#[derive(Default)]
+pub struct Directory(pub HashMap<String, String>);
+
+impl FromContextAsync for Lookup {
+    type Rejection = String;
+
+    async fn from_context_async(ctx: &Context) -> Result<Self, Self::Rejection> {
+        yield_now().await;
+        let directory = ctx
+            .get::<Directory>()
+            .ok_or_else(|| "directory is not available".to_string())?;
+        directory
+            .0
+            .get(&ctx.param)
+            .cloned()
+            .map(Lookup)
+            .ok_or_else(|| format!("`{}` not found", ctx.param))
+    }
+}
+
+async fn extract_async<T: FromContextAsync>(ctx: &Context) -> Result<T, DispatchError> {
+    T::from_context_async(ctx)
+        .await
+        .map_err(|rejection| DispatchError::Rejected {
+            extractor: any::type_name::<T>(),
+            reason: rejection.to_string(),
+        })
+}
+
+/// 异步版本的 Handler 处理函数返回一个 Future
+pub trait AsyncHandler<T> {
+    fn call(self, ctx: &Context) -> impl Future<Output = Result<Response, DispatchError>>;
+}
+
+/// 与同步版本一样用宏为 0 到 12 个参数的函数实现
+/// 参数依次 await 提取 任意一个失败就直接返回
+macro_rules! impl_async_handler {
+    ($($ty:ident),*) => {
+        #[allow(non_snake_case)] // 宏里复用类型名作为变量名
+        impl<F, Fut, R, $($ty,)*> AsyncHandler<($($ty,)*)> for F
+        where
+            F: Fn($($ty),*) -> Fut,
+            Fut: Future<Output = R>,
+            R: IntoResponse,
+            $($ty: FromContextAsync,)*
+        {
+            #[allow(unused_variables)] // 零个参数时 ctx 未使用
+            fn call(self, ctx: &Context) -> impl Future<Output = Result<Response, DispatchError>> {
+                async move {
+                    $(let $ty = extract_async::<$ty>(ctx).await?;)*
+                    Ok(self($($ty),*).await.into_response())
+                }
+            }
+        }
+    };
+}
+
+impl_async_handler!();
+impl_async_handler!(T1);
+impl_async_handler!(T1, T2);
+impl_async_handler!(T1, T2, T3);
+impl_async_handler!(T1, T2, T3, T4);
+impl_async_handler!(T1, T2, T3, T4, T5);
+impl_async_handler!(T1, T2, T3, T4, T5, T6);
+impl_async_handler!(T1, T2, T3, T4, T5, T6, T7);
+impl_async_handler!(T1, T2, T3, T4, T5, T6, T7, T8);
+impl_async_handler!(T1, T2, T3, T4, T5, T6, T7, T8, T9);
+impl_async_handler!(T1, T2, T3, T4, T5, T6, T7, T8, T9, T10);
+impl_async_handler!(T1, T2, T3, T4, T5, T6, T7, T8, T9, T10, T11);
+impl_async_handler!(T1, T2, T3, T4, T5, T6, T7, T8, T9, T10, T11, T12);
+
+/// 异步版本的 [`dispatch`](super::dispatch) 返回的 Future 需要交给执行器运行
+pub async fn dispatch_async<T>(
+    ctx: &Context,
+    handler: impl AsyncHandler<T>,
+) -> Result<Response, DispatchError> {
+    handler.call(ctx).await
+}
+
+/// 把异步处理函数包装成同步的 [`Handler`] 在内部用 [`block_on`] 运行
+/// 这样就能注册到同步的 [`Router`] 中
+#[derive(Clone)]
+pub struct Blocking<H>(pub H);
+
+impl<T, H: AsyncHandler<T>> Handler<T> for Blocking<H> {
+    fn call(self, ctx: &Context) -> Result<Response, DispatchError> {
+        block_on(self.0.call(ctx))
+    }
+}
+
+impl Router {
+    /// 注册异步处理函数 分发时阻塞当前线程直到完成
+    pub fn route_async<T, H>(self, key: impl Into<String>, handler: H) -> Self
+    where
+        T: 'static,
+        H: AsyncHandler<T> + Clone + 'static,
+    {
+        self.route(key, Blocking(handler))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::design_patterns::inversion::extension::State;
+    use crate::design_patterns::inversion::{dispatch, Id, Param, Parsed};
+
+    fn context(param: &str) -> Context {
+        let mut ctx = Context::new(7, param);
+        ctx.insert(10u32);
+        ctx
+    }
+
+    /// 同一组参数 同步和异步的处理函数得到相同的结果
+    #[test]
+    fn async_and_sync_handlers_agree() {
+        fn sync_handler(Id(id): Id, Param(p): Param, State(base): State<u32>) -> String {
+            format!("{}:{}", p, id + base)
+        }
+        async fn async_handler(Id(id): Id, Param(p): Param, State(base): State<u32>) -> String {
+            yield_now().await;
+            format!("{}:{}", p, id + base)
+        }
+
+        let ctx = context("abc");
+        let expected = Ok(Response::Text("abc:17".into()));
+        assert_eq!(dispatch(&ctx, sync_handler), expected);
+        assert_eq!(block_on(dispatch_async(&ctx, async_handler)), expected);
+
+        // 提取失败的结果也一致
+        let sync_parse = |Parsed(n): Parsed<u8>| n;
+        let async_parse = |Parsed(n): Parsed<u8>| async move { n };
+        for param in ["8", "", "256"] {
+            let ctx = context(param);
+            assert_eq!(
+                dispatch(&ctx, sync_parse),
+                block_on(dispatch_async(&ctx, async_parse)),
+                "param {param:?}"
+            );
+        }
+    }
+
+    #[test]
+    fn async_extractor() {
+        let mut ctx = context("alice");
+        ctx.insert(Directory(HashMap::from([(
+            "alice".to_string(),
+            "alice@example.com".to_string(),
+        )])));
+
+        let email = |Lookup(email): Lookup, Id(id): Id| async move { format!("{id} {email}") };
+        assert_eq!(
+            block_on(dispatch_async(&ctx, email)),
+            Ok(Response::Text("7 alice@example.com".into()))
+        );
+
+        ctx.param = "bob".into();
+        let err = block_on(dispatch_async(&ctx, email)).unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            format!(
+                "extractor `{}` rejected: `bob` not found",
+                any::type_name::<Lookup>()
+            )
+        );
+    }
+
+    #[test]
+    fn async_handler_in_router() {
+        let router = Router::new()
+            .route("sync", |Id(id): Id| id * 2)
+            .route_async("async", |Id(id): Id| async move {
+                yield_now().await;
+                id * 2
+            });
+
+        let ctx = context("");
+        assert_eq!(
+            router.dispatch("sync", &ctx),
+            router.dispatch("async", &ctx)
+        );
+        assert_eq!(
+            router.dispatch("async", &ctx),
+            Ok(Response::Text("14".into()))
+        );
+    }
+}
@@ -0,0 +1,306 @@
+//! 事件总线
+//! 处理函数按事件类型订阅 发出事件时依次调用该类型的所有订阅者
+//! 订阅者同样是与 Handler 兼容的函数：事件本身通过 [`Event`] 提取 其余参数照常从共享的上下文中提取
+//!
+//! - 优先级高的先执行 优先级相同时按订阅顺序执行
+//! - 一次性订阅者执行一次后自动移除
+//! - 订阅时返回 [`Subscription`] 凭它取消订阅
+
+use std::any::{self, TypeId};
+use std::collections::HashMap;
+use std::fmt;
+use std::ops::Deref;
+
+use super::{
+    boxed, BoxedHandler, Context, ContextParam, DispatchError, FromContextRef, Handler, Response,
+};
+
+/// 正在分发的事件 包一层避免与上下文中同类型的普通数据冲突
+struct Emitted<E>(E);
+
+/// 提取器：借用正在分发的事件
+pub struct Event<'a, E>(pub &'a E);
+
+impl<E> Deref for Event<'_, E> {
+    type Target = E;
+
+    fn deref(&self) -> &Self::Target {
+        self.0
+    }
+}
+
+/// 订阅者想要提取的事件类型与正在分发的事件不一致（或者不在分发过程中）
+#[derive(Debug, Clone, PartialEq)]
+pub struct EventMismatch {
+    pub expected: &'static str,
+}
+
+impl fmt::Display for EventMismatch {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "event `{}` is not being emitted", self.expected)
+    }
+}
+
+impl<'a, E: 'static> FromContextRef<'a> for Event<'a, E> {
+    type Rejection = EventMismatch;
+
+    fn from_context_ref(ctx: &'a Context) -> Result<Self, Self::Rejection> {
+        ctx.get::<Emitted<E>>()
+            .map(|Emitted(event)| Event(event))
+            .ok_or(EventMismatch {
+                expected: any::type_name::<E>(),
+            })
+    }
+}
+
+impl<E: 'static> ContextParam for Event<'_, E> {
+    type Item<'a> = Event<'a, E>;
+}
+
+/// 订阅选项
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Listen {
+    priority: i32,
+    once: bool,
+}
+
+impl Listen {
+    /// 数值越大越先执行 默认为 0
+    pub fn priority(mut self, priority: i32) -> Self {
+        self.priority = priority;
+        self
+    }
+
+    /// 只执行一次
+    pub fn once(mut self) -> Self {
+        self.once = true;
+        self
+    }
+}
+
+/// 订阅凭证 用于取消订阅
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Subscription {
+    event: TypeId,
+    id: u64,
+}
+
+struct Listener {
+    id: u64,
+    options: Listen,
+    run: BoxedHandler,
+}
+
+#[derive(Default)]
+pub struct EventBus {
+    /// 每种事件的订阅者 已按执行顺序排列
+    listeners: HashMap<TypeId, Vec<Listener>>,
+    next_id: u64,
+}
+
+impl EventBus {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn subscribe<E, T, H>(&mut self, handler: H) -> Subscription
+    where
+        E: 'static,
+        T: 'static,
+        H: Handler<T> + Clone + 'static,
+    {
+        self.subscribe_with::<E, T, H>(Listen::default(), handler)
+    }
+
+    pub fn subscribe_with<E, T, H>(&mut self, options: Listen, handler: H) -> Subscription
+    where
+        E: 'static,
+        T: 'static,
+        H: Handler<T> + Clone + 'static,
+    {
+        let id = self.next_id;
+        self.next_id += 1;
+
+        let listeners = self.listeners.entry(TypeId::of::<E>()).or_default();
+        // 插到所有优先级不低于它的订阅者之后 保证同优先级按订阅顺序执行
+        let pos =
+            listeners.partition_point(|listener| listener.options.priority >= options.priority);
+        listeners.insert(
+            pos,
+            Listener {
+                id,
+                options,
+                run: boxed(handler),
+            },
+        );
+
+        Subscription {
+            event: TypeId::of::<E>(),
+            id,
+        }
+    }
+
+    /// 取消订阅 订阅者已经不存在时（如一次性订阅者已执行）返回 false
+    pub fn unsubscribe(&mut self, subscription: Subscription) -> bool {
+        let Some(listeners) = self.listeners.get_mut(&subscription.event) else {
+            return false;
+        };
+        let before = listeners.len();
+        listeners.retain(|listener| listener.id != subscription.id);
+        before != listeners.len()
+    }
+
+    pub fn listener_count<E: 'static>(&self) -> usize {
+        self.listeners.get(&TypeId::of::<E>()).map_or(0, Vec::len)
+    }
+
+    /// 发出事件 按顺序调用所有订阅者 返回每个订阅者的结果
+    ///
+    /// 分发期间事件存放在上下文中供 [`Event`] 提取 结束后移除
+    /// 某个订阅者失败不影响后续订阅者
+    pub fn emit<E: 'static>(
+        &mut self,
+        ctx: &mut Context,
+        event: E,
+    ) -> Vec<Result<Response, DispatchError>> {
+        let Some(listeners) = self.listeners.get_mut(&TypeId::of::<E>()) else {
+            return Vec::new();
+        };
+
+        ctx.insert(Emitted(event));
+        let results = listeners
+            .iter()
+            .map(|listener| (listener.run)(ctx))
+            .collect();
+        listeners.retain(|listener| !listener.options.once);
+        ctx.remove::<Emitted<E>>();
+        results
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::cell::RefCell;
+
+    use super::*;
+    use crate::design_patterns::inversion::extension::Res;
+    use crate::design_patterns::inversion::Id;
+
+    struct Clicked {
+        x: i32,
+        y: i32,
+    }
+
+    struct Closed;
+
+    type Trace = RefCell<Vec<String>>;
+
+    fn context() -> Context {
+        let mut ctx = Context::new(9, "");
+        ctx.insert(Trace::default());
+        ctx
+    }
+
+    fn trace(ctx: &Context) -> Vec<String> {
+        ctx.get::<Trace>().unwrap().borrow().clone()
+    }
+
+    #[test]
+    fn typed_events() {
+        let mut bus = EventBus::new();
+        bus.subscribe::<Clicked, _, _>(|event: Event<Clicked>, trace: Res<Trace>| {
+            trace
+                .borrow_mut()
+                .push(format!("clicked {},{}", event.x, event.y))
+        });
+        bus.subscribe::<Closed, _, _>(|trace: Res<Trace>, Id(id): Id| {
+            trace.borrow_mut().push(format!("closed {id}"))
+        });
+
+        let mut ctx = context();
+        assert_eq!(bus.emit(&mut ctx, Clicked { x: 1, y: 2 }).len(), 1);
+        assert_eq!(bus.emit(&mut ctx, Closed).len(), 1);
+        // 没有订阅者的事件
+        assert!(bus.emit(&mut ctx, 42u8).is_empty());
+
+        assert_eq!(trace(&ctx), ["clicked 1,2", "closed 9"]);
+        // 分发结束后事件不再留在上下文中
+        assert!(ctx.get::<Emitted<Clicked>>().is_none());
+    }
+
+    #[test]
+    fn priority_order() {
+        let mut bus = EventBus::new();
+        for (name, priority) in [("low", -1), ("first", 0), ("high", 10), ("second", 0)] {
+            bus.subscribe_with::<Closed, _, _>(
+                Listen::default().priority(priority),
+                move |trace: Res<Trace>| trace.borrow_mut().push(name.to_string()),
+            );
+        }
+
+        let mut ctx = context();
+        bus.emit(&mut ctx, Closed);
+        assert_eq!(trace(&ctx), ["high", "first", "second", "low"]);
+    }
+
+    #[test]
+    fn once_listener() {
+        let mut bus = EventBus::new();
+        let once = bus.subscribe_with::<Closed, _, _>(Listen::default().once(), || "once");
+        bus.subscribe::<Closed, _, _>(|| "always");
+
+        let mut ctx = context();
+        assert_eq!(
+            bus.emit(&mut ctx, Closed),
+            [
+                Ok(Response::Text("once".into())),
+                Ok(Response::Text("always".into()))
+            ]
+        );
+        assert_eq!(
+            bus.emit(&mut ctx, Closed),
+            [Ok(Response::Text("always".into()))]
+        );
+        assert_eq!(bus.listener_count::<Closed>(), 1);
+        // 已经自动移除
+        assert!(!bus.unsubscribe(once));
+    }
+
+    #[test]
+    fn unsubscribe() {
+        let mut bus = EventBus::new();
+        let a = bus.subscribe::<Closed, _, _>(|| "a");
+        let b = bus.subscribe::<Closed, _, _>(|| "b");
+        assert_eq!(bus.listener_count::<Closed>(), 2);
+
+        assert!(bus.unsubscribe(a));
+        assert!(!bus.unsubscribe(a));
+        assert_eq!(
+            bus.emit(&mut context(), Closed),
+            [Ok(Response::Text("b".into()))]
+        );
+
+        assert!(bus.unsubscribe(b));
+        assert!(bus.emit(&mut context(), Closed).is_empty());
+    }
+
+    /// 提取失败只影响当前订阅者
+    #[test]
+    fn failed_listener() {
+        let mut bus = EventBus::new();
+        // 订阅的是 Closed 却想提取 Clicked
+        bus.subscribe::<Closed, _, _>(|event: Event<Clicked>| event.x);
+        bus.subscribe::<Closed, _, _>(|| "still runs");
+
+        let results = bus.emit(&mut context(), Closed);
+        let Err(DispatchError::Rejected { reason, .. }) = &results[0] else {
+            panic!("expected rejection, got {:?}", results[0]);
+        };
+        assert!(reason.starts_with("event `"), "{reason}");
+        assert!(
+            reason.ends_with("Clicked` is not being emitted"),
+            "{reason}"
+        );
+        assert_eq!(results[1], Ok(Response::Text("still runs".into())));
+    }
+}
@@ -0,0 +1,220 @@
+//! 类型索引的扩展存储（状态注入）
+//! 上下文里除了固定的 id 和 param 之外 还可以按类型存放任意数据 如数据库连接 配置 计数器
+//! 处理函数通过 [`State`] 或 [`Res`] 声明需要的数据 由框架注入 类似于 Bevy 的 Resource
+//!
+//! 实现方式：以 `TypeId` 为键 `Box<dyn Any>` 为值 取出时再向下转型（downcast）回具体类型
+//! 每种类型最多存放一个值
+
+use std::any::{self, Any, TypeId};
+use std::collections::HashMap;
+use std::fmt;
+use std::ops::Deref;
+
+use super::{Context, ContextParam, FromContextRef, TryFromContext};
+
+#[derive(Default)]
+pub struct Extensions {
+    map: HashMap<TypeId, Box<dyn Any>>,
+}
+
+impl Extensions {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// 链式存入
+    pub fn with<T: 'static>(mut self, value: T) -> Self {
+        self.insert(value);
+        self
+    }
+
+    /// 存入一个值 如果该类型已经存在 返回旧值
+    pub fn insert<T: 'static>(&mut self, value: T) -> Option<T> {
+        self.map
+            .insert(TypeId::of::<T>(), Box::new(value))
+            .and_then(|old| old.downcast().ok())
+            .map(|old: Box<T>| *old)
+    }
+
+    pub fn get<T: 'static>(&self) -> Option<&T> {
+        self.map
+            .get(&TypeId::of::<T>())
+            .and_then(|value| value.downcast_ref())
+    }
+
+    pub fn get_mut<T: 'static>(&mut self) -> Option<&mut T> {
+        self.map
+            .get_mut(&TypeId::of::<T>())
+            .and_then(|value| value.downcast_mut())
+    }
+
+    pub fn remove<T: 'static>(&mut self) -> Option<T> {
+        self.map
+            .remove(&TypeId::of::<T>())
+            .and_then(|value| value.downcast().ok())
+            .map(|value: Box<T>| *value)
+    }
+
+    pub fn contains<T: 'static>(&self) -> bool {
+        self.map.contains_key(&TypeId::of::<T>())
+    }
+
+    pub fn len(&self) -> usize {
+        self.map.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.map.is_empty()
+    }
+}
+
+/// 上下文中没有需要的扩展数据
+#[derive(Debug, Clone, PartialEq)]
+pub struct MissingExtension {
+    pub type_name: &'static str,
+}
+
+impl MissingExtension {
+    fn of<T>() -> Self {
+        MissingExtension {
+            type_name: any::type_name::<T>(),
+        }
+    }
+}
+
+impl fmt::Display for MissingExtension {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "missing extension `{}`", self.type_name)
+    }
+}
+
+/// 按值注入 取出时 clone 一份（类似 Axum 的 State）
+/// 适合本身就是共享句柄的类型 如 `Rc<T>` `Arc<Mutex<T>>` 或者很小的配置
+pub struct State<T>(pub T);
+
+impl<T: Clone + 'static> TryFromContext for State<T> {
+    type Rejection = MissingExtension;
+
+    fn try_from_context(ctx: &Context) -> Result<Self, Self::Rejection> {
+        ctx.get::<T>()
+            .cloned()
+            .map(State)
+            .ok_or_else(MissingExtension::of::<T>)
+    }
+}
+
+/// 按引用注入 不需要 clone（类似 Bevy 的 Res）
+/// 需要修改时配合内部可变性使用 如 `Res<Cell<u32>>`
+pub struct Res<'a, T>(pub &'a T);
+
+impl<T> Deref for Res<'_, T> {
+    type Target = T;
+
+    fn deref(&self) -> &Self::Target {
+        self.0
+    }
+}
+
+impl<'a, T: 'static> FromContextRef<'a> for Res<'a, T> {
+    type Rejection = MissingExtension;
+
+    fn from_context_ref(ctx: &'a Context) -> Result<Self, Self::Rejection> {
+        ctx.get::<T>()
+            .map(Res)
+            .ok_or_else(MissingExtension::of::<T>)
+    }
+}
+
+impl<T: 'static> ContextParam for Res<'_, T> {
+    type Item<'a> = Res<'a, T>;
+}
+
+#[cfg(test)]
+mod test {
+    use std::cell::{Cell, RefCell};
+    use std::rc::Rc;
+
+    use super::*;
+    use crate::design_patterns::inversion::{dispatch, DispatchError, Id, Response};
+
+    #[derive(Debug, Clone, PartialEq)]
+    struct Config {
+        greeting: &'static str,
+    }
+
+    #[test]
+    fn insert_get_remove() {
+        let mut extensions = Extensions::new();
+        assert!(extensions.is_empty());
+
+        assert_eq!(extensions.insert(1u32), None);
+        assert_eq!(extensions.insert("text"), None);
+        assert_eq!(extensions.insert(2u32), Some(1));
+        assert_eq!(extensions.len(), 2);
+
+        assert_eq!(extensions.get::<u32>(), Some(&2));
+        // 类型不同即为不同的键 u64 没有存过
+        assert_eq!(extensions.get::<u64>(), None);
+
+        *extensions.get_mut::<u32>().unwrap() += 1;
+        assert_eq!(extensions.remove::<u32>(), Some(3));
+        assert!(!extensions.contains::<u32>());
+        assert_eq!(extensions.remove::<u32>(), None);
+        assert!(extensions.contains::<&str>());
+    }
+
+    #[test]
+    fn state_and_res() {
+        let mut ctx = Context::new(1, "world");
+        ctx.insert(Config { greeting: "hello" });
+        ctx.insert(Cell::new(0u32));
+
+        let greet =
+            |State(config): State<Config>, param: &str| format!("{} {}", config.greeting, param);
+        assert_eq!(
+            dispatch(&ctx, greet),
+            Ok(Response::Text("hello world".into()))
+        );
+
+        // Res 借用上下文中的数据 配合 Cell 修改计数
+        let count = |counter: Res<Cell<u32>>, Id(id): Id| counter.set(counter.get() + id);
+        dispatch(&ctx, count).unwrap();
+        dispatch(&ctx, count).unwrap();
+        assert_eq!(ctx.get::<Cell<u32>>().map(Cell::get), Some(2));
+    }
+
+    /// 共享句柄：State 中 clone 的是 Rc 指向同一份数据
+    #[test]
+    fn shared_handle() {
+        let mut ctx = Context::new(1, "");
+        let log: Rc<RefCell<Vec<u32>>> = Rc::default();
+        ctx.insert(Rc::clone(&log));
+
+        let record =
+            |State(log): State<Rc<RefCell<Vec<u32>>>>, Id(id): Id| log.borrow_mut().push(id);
+        dispatch(&ctx, record).unwrap();
+        dispatch(&ctx, record).unwrap();
+        assert_eq!(*log.borrow(), [1, 1]);
+    }
+
+    #[test]
+    fn missing_extension() {
+        let ctx = Context::new(1, "");
+
+        let err = dispatch(&ctx, |_: Res<Config>| {}).unwrap_err();
+        assert_eq!(
+            err,
+            DispatchError::Rejected {
+                extractor: any::type_name::<Res<Config>>(),
+                reason: format!("missing extension `{}`", any::type_name::<Config>()),
+            }
+        );
+
+        assert!(dispatch(&ctx, |_: State<Config>| {}).is_err());
+        // 可选的状态
+        assert_eq!(
+            dispatch(&ctx, |config: Option<State<Config>>| config.is_none()),
+            Ok(Response::Text("true".into()))
+        );
+    }
+}
@@ -0,0 +1,341 @@
+//! 中间件
+//! 在处理函数的前后插入通用逻辑 如日志 计时 鉴权
+//!
+//! 多个中间件像洋葱一样一层层包裹住处理函数（类似 Axum / Tower 的 Layer）：
+//!
+//! ```text
+//! before(A) -> before(B) -> handler -> after(B) -> after(A)
+//! ```
+//!
+//! - before 可以修改上下文 也可以短路（直接给出结果 不再调用内层）
+//! - after 可以查看和修改结果
+//! - 短路时 只有 before 返回了 Continue 的外层中间件会执行 after
+//!   短路的中间件自己不会执行 after（结果就是它给出的）
+
+use std::cell::RefCell;
+use std::ops::ControlFlow;
+use std::rc::Rc;
+use std::time::{Duration, Instant};
+
+use super::router::Router;
+use super::{Context, DispatchError, Response};
+
+/// 一次分发的结果
+pub type Outcome = Result<Response, DispatchError>;
+
+pub trait Middleware {
+    /// 调用处理函数之前执行 返回 `Break(outcome)` 表示短路
+    fn before(&self, _key: &str, _ctx: &mut Context) -> ControlFlow<Outcome> {
+        ControlFlow::Continue(())
+    }
+
+    /// 得到结果之后执行（包括内层短路或失败的结果）
+    fn after(&self, _key: &str, _ctx: &mut Context, _outcome: &mut Outcome) {}
+}
+
+/// 路由 + 中间件栈
+pub struct Pipeline {
+    router: Router,
+    /// 按注册顺序排列 先注册的在最外层
+    layers: Vec<Box<dyn Middleware>>,
+}
+
+impl Pipeline {
+    pub fn new(router: Router) -> Self {
+        Pipeline {
+            router,
+            layers: Vec::new(),
+        }
+    }
+
+    pub fn layer(mut self, middleware: impl Middleware + 'static) -> Self {
+        self.layers.push(Box::new(middleware));
+        self
+    }
+
+    pub fn handle(&self, key: &str, ctx: &mut Context) -> Outcome {
+        let mut entered = 0;
+        let mut outcome = None;
+        for layer in &self.layers {
+            if let ControlFlow::Break(short_circuit) = layer.before(key, ctx) {
+                outcome = Some(short_circuit);
+                break;
+            }
+            entered += 1;
+        }
+
+        let mut outcome = match outcome {
+            Some(outcome) => outcome,
+            None => self.router.dispatch(key, ctx),
+        };
+        for layer in self.layers[..entered].iter().rev() {
+            layer.after(key, ctx, &mut outcome);
+        }
+        outcome
+    }
+}
+
+/// 日志中间件 记录每次请求的进出
+/// 日志写入共享的缓冲区 clone 出来的实例共享同一份日志
+#[derive(Clone, Default)]
+pub struct Logging {
+    lines: Rc<RefCell<Vec<String>>>,
+}
+
+impl Logging {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn lines(&self) -> Vec<String> {
+        self.lines.borrow().clone()
+    }
+}
+
+impl Middleware for Logging {
+    fn before(&self, key: &str, ctx: &mut Context) -> ControlFlow<Outcome> {
+        self.lines
+            .borrow_mut()
+            .push(format!("--> {key} id={} param={:?}", ctx.id, ctx.param));
+        ControlFlow::Continue(())
+    }
+
+    fn after(&self, key: &str, _ctx: &mut Context, outcome: &mut Outcome) {
+        let line = match outcome {
+            Ok(response) => format!("<-- {key} ok {response:?}"),
+            Err(err) => format!("<-- {key} err {err}"),
+        };
+        self.lines.borrow_mut().push(line);
+    }
+}
+
+/// 计时中间件 处理耗时写入上下文的 [`Elapsed`]
+pub struct Timing;
+
+/// 开始时间 只在计时期间存在于上下文中
+struct Started(Instant);
+
+/// 最近一次请求的耗时
+#[derive(Debug, Clone, Copy)]
+pub struct Elapsed(pub Duration);
+
+impl Middleware for Timing {
+    fn before(&self, _key: &str, ctx: &mut Context) -> ControlFlow<Outcome> {
+        ctx.insert(Started(Instant::now()));
+        ControlFlow::Continue(())
+    }
+
+    fn after(&self, _key: &str, ctx: &mut Context, _outcome: &mut Outcome) {
+        if let Some(Started(start)) = ctx.remove::<Started>() {
+            ctx.insert(Elapsed(start.elapsed()));
+        }
+    }
+}
+
+/// 请求携带的令牌 由调用方注入上下文
+#[derive(Debug, Clone, PartialEq)]
+pub struct Token(pub String);
+
+/// 鉴权中间件 令牌不匹配时短路 不会调用处理函数
+pub struct AuthCheck {
+    token: String,
+}
+
+impl AuthCheck {
+    pub fn new(token: impl Into<String>) -> Self {
+        AuthCheck {
+            token: token.into(),
+        }
+    }
+}
+
+impl Middleware for AuthCheck {
+    fn before(&self, _key: &str, ctx: &mut Context) -> ControlFlow<Outcome> {
+        let reason = match ctx.get::<Token>() {
+            Some(Token(token)) if *token == self.token => return ControlFlow::Continue(()),
+            Some(_) => "invalid token",
+            None => "missing token",
+        };
+        ControlFlow::Break(Err(DispatchError::Aborted {
+            layer: "AuthCheck",
+            reason: reason.to_string(),
+        }))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::cell::Cell;
+
+    use super::*;
+    use crate::design_patterns::inversion::extension::Res;
+    use crate::design_patterns::inversion::Id;
+
+    fn router() -> Router {
+        Router::new()
+            .route("echo", |param: &str| param.to_string())
+            .route("calls", |calls: Res<Cell<u32>>, Id(id): Id| {
+                calls.set(calls.get() + 1);
+                id
+            })
+    }
+
+    fn context() -> Context {
+        let mut ctx = Context::new(1, "hi");
+        ctx.insert(Cell::new(0u32));
+        ctx
+    }
+
+    /// 按洋葱顺序执行
+    #[test]
+    fn onion_order() {
+        struct Trace(&'static str, Rc<RefCell<Vec<String>>>);
+
+        impl Middleware for Trace {
+            fn before(&self, _key: &str, _ctx: &mut Context) -> ControlFlow<Outcome> {
+                self.1.borrow_mut().push(format!("before {}", self.0));
+                ControlFlow::Continue(())
+            }
+
+            fn after(&self, _key: &str, _ctx: &mut Context, _outcome: &mut Outcome) {
+                self.1.borrow_mut().push(format!("after {}", self.0));
+            }
+        }
+
+        let trace = Rc::new(RefCell::new(Vec::new()));
+        let pipeline = Pipeline::new(router())
+            .layer(Trace("a", trace.clone()))
+            .layer(Trace("b", trace.clone()));
+
+        assert!(pipeline.handle("echo", &mut context()).is_ok());
+        assert_eq!(
+            *trace.borrow(),
+            ["before a", "before b", "after b", "after a"]
+        );
+    }
+
+    /// 短路的中间件和它内层的中间件都不执行 after
+    #[test]
+    fn short_circuit_skips_own_after() {
+        struct Trace(&'static str, bool, Rc<RefCell<Vec<String>>>);
+
+        impl Middleware for Trace {
+            fn before(&self, _key: &str, _ctx: &mut Context) -> ControlFlow<Outcome> {
+                self.2.borrow_mut().push(format!("before {}", self.0));
+                if self.1 {
+                    ControlFlow::Break(Ok(Response::Text(self.0.into())))
+                } else {
+                    ControlFlow::Continue(())
+                }
+            }
+
+            fn after(&self, _key: &str, _ctx: &mut Context, _outcome: &mut Outcome) {
+                self.2.borrow_mut().push(format!("after {}", self.0));
+            }
+        }
+
+        let trace = Rc::new(RefCell::new(Vec::new()));
+        let pipeline = Pipeline::new(router())
+            .layer(Trace("a", false, trace.clone()))
+            .layer(Trace("b", true, trace.clone()))
+            .layer(Trace("c", false, trace.clone()));
+
+        assert_eq!(
+            pipeline.handle("echo", &mut context()),
+            Ok(Response::Text("b".into()))
+        );
+        assert_eq!(*trace.borrow(), ["before a", "before b", "after a"]);
+    }
+
+    /// before 修改上下文 after 修改结果
+    #[test]
+    fn modify_context_and_outcome() {
+        struct Shout;
+
+        impl Middleware for Shout {
+            fn before(&self, _key: &str, ctx: &mut Context) -> ControlFlow<Outcome> {
+                ctx.param = ctx.param.to_uppercase();
+                ControlFlow::Continue(())
+            }
+
+            fn after(&self, _key: &str, _ctx: &mut Context, outcome: &mut Outcome) {
+                if let Ok(Response::Text(text)) = outcome {
+                    text.push('!');
+                }
+            }
+        }
+
+        let pipeline = Pipeline::new(router()).layer(Shout);
+        assert_eq!(
+            pipeline.handle("echo", &mut context()),
+            Ok(Response::Text("HI!".into()))
+        );
+    }
+
+    #[test]
+    fn logging() {
+        let logging = Logging::new();
+        let pipeline = Pipeline::new(router()).layer(logging.clone());
+
+        let mut ctx = context();
+        pipeline.handle("echo", &mut ctx).unwrap();
+        pipeline.handle("nope", &mut ctx).unwrap_err();
+
+        assert_eq!(
+            logging.lines(),
+            [
+                "--> echo id=1 param=\"hi\"",
+                "<-- echo ok Text(\"hi\")",
+                "--> nope id=1 param=\"hi\"",
+                "<-- nope err no handler registered for `nope`",
+            ]
+        );
+    }
+
+    #[test]
+    fn timing() {
+        let pipeline = Pipeline::new(
+            Router::new().route("sleep", || std::thread::sleep(Duration::from_millis(5))),
+        )
+        .layer(Timing);
+
+        let mut ctx = context();
+        assert!(ctx.get::<Elapsed>().is_none());
+        pipeline.handle("sleep", &mut ctx).unwrap();
+
+        let Elapsed(elapsed) = *ctx.get::<Elapsed>().unwrap();
+        assert!(elapsed >= Duration::from_millis(5));
+        // 计时用的临时数据已经清理
+        assert!(ctx.get::<Started>().is_none());
+    }
+
+    /// 鉴权失败时短路：处理函数和内层中间件都不会执行 外层中间件仍然能看到结果
+    #[test]
+    fn auth_short_circuit() {
+        let logging = Logging::new();
+        let inner = Logging::new();
+        let pipeline = Pipeline::new(router())
+            .layer(logging.clone())
+            .layer(AuthCheck::new("secret"))
+            .layer(inner.clone());
+
+        let mut ctx = context();
+        let err = pipeline.handle("calls", &mut ctx).unwrap_err();
+        assert_eq!(err.to_string(), "aborted by `AuthCheck`: missing token");
+
+        ctx.insert(Token("guess".into()));
+        let err = pipeline.handle("calls", &mut ctx).unwrap_err();
+        assert_eq!(err.to_string(), "aborted by `AuthCheck`: invalid token");
+        assert_eq!(ctx.get::<Cell<u32>>().unwrap().get(), 0);
+        assert!(inner.lines().is_empty());
+        assert_eq!(logging.lines().len(), 4);
+
+        ctx.insert(Token("secret".into()));
+        assert_eq!(
+            pipeline.handle("calls", &mut ctx),
+            Ok(Response::Text("1".into()))
+        );
+        assert_eq!(ctx.get::<Cell<u32>>().unwrap().get(), 1);
+        assert_eq!(inner.lines().len(), 2);
+    }
+}
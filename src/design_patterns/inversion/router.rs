@@ -0,0 +1,118 @@
+//! 路由
+//! 按字符串注册处理函数 运行时再按字符串找到并调用
+//!
+//! 不同的处理函数是不同的类型（且 Handler 的泛型参数也不同） 无法直接放进同一个容器
+//! 因此注册时先把它们包装成统一签名的闭包 `Fn(&Context) -> Response` 再做类型擦除
+
+use std::collections::HashMap;
+
+use super::{boxed, BoxedHandler, Context, DispatchError, Handler, Response};
+
+#[derive(Default)]
+pub struct Router {
+    routes: HashMap<String, BoxedHandler>,
+}
+
+impl Router {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// 注册处理函数 重复注册同一个 key 视为编程错误 直接 panic
+    ///
+    /// `Handler::call` 会消耗 self 而路由需要多次调用 所以这里要求 Clone
+    /// 函数指针以及只捕获了可 Clone 变量的闭包都满足这个要求
+    pub fn route<T, H>(mut self, key: impl Into<String>, handler: H) -> Self
+    where
+        T: 'static,
+        H: Handler<T> + Clone + 'static,
+    {
+        let key = key.into();
+        assert!(
+            !self.routes.contains_key(&key),
+            "route `{key}` is already registered"
+        );
+        self.routes.insert(key, boxed(handler));
+        self
+    }
+
+    pub fn contains(&self, key: &str) -> bool {
+        self.routes.contains_key(key)
+    }
+
+    /// 按 key 找到处理函数并调用
+    pub fn dispatch(&self, key: &str, ctx: &Context) -> Result<Response, DispatchError> {
+        let handler = self
+            .routes
+            .get(key)
+            .ok_or_else(|| DispatchError::NotFound(key.to_string()))?;
+        handler(ctx)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::design_patterns::inversion::{Id, Param, Parsed};
+
+    fn context() -> Context {
+        Context::new(7, "hello")
+    }
+
+    #[test]
+    fn route_and_dispatch() {
+        let prefix = String::from(">> ");
+        let router = Router::new()
+            .route("ping", || "pong")
+            .route("id", |Id(id): Id| id)
+            .route("echo", move |Param(p): Param| format!("{prefix}{p}"))
+            .route("unit", |_: Id, _: Param| {})
+            .route("borrow", |param: &str| param.to_uppercase());
+
+        let ctx = context();
+        assert!(router.contains("ping"));
+        assert_eq!(
+            router.dispatch("ping", &ctx),
+            Ok(Response::Text("pong".into()))
+        );
+        assert_eq!(router.dispatch("id", &ctx), Ok(Response::Text("7".into())));
+        assert_eq!(
+            router.dispatch("echo", &ctx),
+            Ok(Response::Text(">> hello".into()))
+        );
+        assert_eq!(router.dispatch("unit", &ctx), Ok(Response::Empty));
+        assert_eq!(
+            router.dispatch("borrow", &ctx),
+            Ok(Response::Text("HELLO".into()))
+        );
+
+        // 同一个处理函数可以被多次调用
+        assert_eq!(router.dispatch("id", &ctx), Ok(Response::Text("7".into())));
+    }
+
+    #[test]
+    fn not_found() {
+        let router = Router::new().route("ping", || "pong");
+        let err = router.dispatch("pong", &context()).unwrap_err();
+        assert_eq!(err, DispatchError::NotFound("pong".into()));
+        assert_eq!(err.to_string(), "no handler registered for `pong`");
+    }
+
+    #[test]
+    fn rejected() {
+        let router = Router::new().route("square", |Parsed(n): Parsed<u32>| n * n);
+        let err = router.dispatch("square", &context()).unwrap_err();
+        assert!(matches!(err, DispatchError::Rejected { .. }));
+        assert!(err
+            .to_string()
+            .ends_with("invalid param `hello`: invalid digit found in string"));
+    }
+
+    #[test]
+    #[should_panic(expected = "already registered")]
+    fn duplicate_route() {
+        let _ = Router::new()
+            .route("ping", || "pong")
+            .route("ping", || "pong");
+    }
+}
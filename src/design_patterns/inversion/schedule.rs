@@ -0,0 +1,410 @@
+//! 系统调度（简化版的 Bevy Schedule）
+//! 与 Handler 兼容的函数都可以注册为系统（System） 由调度器在对应阶段（Stage）统一调用
+//! 所有系统共享同一个上下文 通过 [`Res`](super::extension::Res) 等提取器访问其中的数据
+//!
+//! 同一阶段内的执行顺序通过 before / after 声明 调度器做拓扑排序：
+//! - 没有约束的系统按注册顺序执行
+//! - 约束形成环时报错 并给出环上的系统
+
+use std::collections::HashMap;
+use std::fmt;
+
+use super::{boxed, BoxedHandler, Context, DispatchError, Handler, Response};
+
+/// 执行阶段 startup 只执行一次 update 每帧执行 shutdown 在退出前执行一次
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Stage {
+    Startup,
+    Update,
+    Shutdown,
+}
+
+/// 一个系统 以及它的顺序约束
+pub struct System {
+    name: String,
+    before: Vec<String>,
+    after: Vec<String>,
+    run: BoxedHandler,
+}
+
+impl System {
+    pub fn new<T, H>(name: impl Into<String>, handler: H) -> Self
+    where
+        T: 'static,
+        H: Handler<T> + Clone + 'static,
+    {
+        System {
+            name: name.into(),
+            before: Vec::new(),
+            after: Vec::new(),
+            run: boxed(handler),
+        }
+    }
+
+    /// 在指定系统之前执行
+    pub fn before(mut self, name: impl Into<String>) -> Self {
+        self.before.push(name.into());
+        self
+    }
+
+    /// 在指定系统之后执行
+    pub fn after(mut self, name: impl Into<String>) -> Self {
+        self.after.push(name.into());
+        self
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum ScheduleError {
+    /// 同一阶段内出现重名系统
+    Duplicate { stage: Stage, system: String },
+    /// 约束中引用了同一阶段内不存在的系统
+    Unknown { system: String, dependency: String },
+    /// 约束形成了环 按顺序列出环上的系统 首尾相同
+    Cycle(Vec<String>),
+    /// 系统执行失败（提取参数失败等）
+    Failed {
+        system: String,
+        error: DispatchError,
+    },
+}
+
+impl fmt::Display for ScheduleError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ScheduleError::Duplicate { stage, system } => {
+                write!(f, "system `{system}` is registered twice in {stage:?}")
+            }
+            ScheduleError::Unknown { system, dependency } => {
+                write!(
+                    f,
+                    "system `{system}` depends on unknown system `{dependency}`"
+                )
+            }
+            ScheduleError::Cycle(path) => write!(f, "ordering cycle: {}", path.join(" -> ")),
+            ScheduleError::Failed { system, error } => {
+                write!(f, "system `{system}` failed: {error}")
+            }
+        }
+    }
+}
+
+impl std::error::Error for ScheduleError {}
+
+#[derive(Default)]
+pub struct Schedule {
+    stages: HashMap<Stage, Vec<System>>,
+}
+
+impl Schedule {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn add_system(mut self, stage: Stage, system: System) -> Self {
+        self.stages.entry(stage).or_default().push(system);
+        self
+    }
+
+    /// 计算某个阶段的执行顺序（Kahn 拓扑排序）
+    pub fn order(&self, stage: Stage) -> Result<Vec<&str>, ScheduleError> {
+        let systems = match self.stages.get(&stage) {
+            Some(systems) => systems,
+            None => return Ok(Vec::new()),
+        };
+
+        let mut index = HashMap::new();
+        for (i, system) in systems.iter().enumerate() {
+            if index.insert(system.name.as_str(), i).is_some() {
+                return Err(ScheduleError::Duplicate {
+                    stage,
+                    system: system.name.clone(),
+                });
+            }
+        }
+        let lookup = |system: &System, dependency: &String| {
+            index
+                .get(dependency.as_str())
+                .copied()
+                .ok_or_else(|| ScheduleError::Unknown {
+                    system: system.name.clone(),
+                    dependency: dependency.clone(),
+                })
+        };
+
+        // edges[i] 中的系统必须在 i 之后执行
+        let mut edges = vec![Vec::new(); systems.len()];
+        let mut in_degree = vec![0; systems.len()];
+        for (i, system) in systems.iter().enumerate() {
+            for dependency in &system.before {
+                let j = lookup(system, dependency)?;
+                edges[i].push(j);
+                in_degree[j] += 1;
+            }
+            for dependency in &system.after {
+                let j = lookup(system, dependency)?;
+                edges[j].push(i);
+                in_degree[i] += 1;
+            }
+        }
+
+        // 每次取出注册顺序最靠前的、没有前置依赖的系统 保证结果稳定
+        let mut order = Vec::with_capacity(systems.len());
+        let mut done = vec![false; systems.len()];
+        while let Some(i) = (0..systems.len()).find(|&i| !done[i] && in_degree[i] == 0) {
+            done[i] = true;
+            order.push(systems[i].name.as_str());
+            for &j in &edges[i] {
+                in_degree[j] -= 1;
+            }
+        }
+
+        if order.len() < systems.len() {
+            let cycle = find_cycle(&edges, &done);
+            return Err(ScheduleError::Cycle(
+                cycle.into_iter().map(|i| systems[i].name.clone()).collect(),
+            ));
+        }
+        Ok(order)
+    }
+
+    /// 按顺序执行某个阶段的全部系统 返回各系统的结果
+    pub fn run_stage(
+        &self,
+        stage: Stage,
+        ctx: &Context,
+    ) -> Result<Vec<(String, Response)>, ScheduleError> {
+        let order = self.order(stage)?;
+        let Some(systems) = self.stages.get(&stage) else {
+            return Ok(Vec::new());
+        };
+        order
+            .into_iter()
+            .map(|name| {
+                let system = systems.iter().find(|system| system.name == name).unwrap();
+                (system.run)(ctx)
+                    .map(|response| (name.to_string(), response))
+                    .map_err(|error| ScheduleError::Failed {
+                        system: name.to_string(),
+                        error,
+                    })
+            })
+            .collect()
+    }
+
+    /// 完整地运行一次：startup 一次 update 若干次 shutdown 一次
+    /// 在执行任何系统之前先检查所有阶段的顺序约束
+    pub fn run(&self, ctx: &Context, updates: usize) -> Result<(), ScheduleError> {
+        for stage in [Stage::Startup, Stage::Update, Stage::Shutdown] {
+            self.order(stage)?;
+        }
+
+        self.run_stage(Stage::Startup, ctx)?;
+        for _ in 0..updates {
+            self.run_stage(Stage::Update, ctx)?;
+        }
+        self.run_stage(Stage::Shutdown, ctx)?;
+        Ok(())
+    }
+}
+
+/// 在拓扑排序剩下的节点里找一个环（深度优先 遇到正在访问的节点即成环）
+fn find_cycle(edges: &[Vec<usize>], done: &[bool]) -> Vec<usize> {
+    fn visit(
+        node: usize,
+        edges: &[Vec<usize>],
+        done: &[bool],
+        on_path: &mut Vec<usize>,
+        visited: &mut [bool],
+    ) -> Option<Vec<usize>> {
+        if let Some(pos) = on_path.iter().position(|&n| n == node) {
+            let mut cycle = on_path[pos..].to_vec();
+            cycle.push(node);
+            return Some(cycle);
+        }
+        if visited[node] {
+            return None;
+        }
+        visited[node] = true;
+        on_path.push(node);
+        for &next in edges[node].iter().filter(|&&next| !done[next]) {
+            if let Some(cycle) = visit(next, edges, done, on_path, visited) {
+                return Some(cycle);
+            }
+        }
+        on_path.pop();
+        None
+    }
+
+    let mut visited = vec![false; edges.len()];
+    (0..edges.len())
+        .filter(|&start| !done[start])
+        .find_map(|start| visit(start, edges, done, &mut Vec::new(), &mut visited))
+        .unwrap_or_default()
+}
+
+#[cfg(test)]
+mod test {
+    use std::cell::{Cell, RefCell};
+
+    use super::*;
+    use crate::design_patterns::inversion::extension::Res;
+
+    /// 记录系统的执行顺序
+    type Trace = RefCell<Vec<&'static str>>;
+
+    fn traced(name: &'static str) -> System {
+        System::new(name, move |trace: Res<Trace>| trace.borrow_mut().push(name))
+    }
+
+    fn world() -> Context {
+        let mut ctx = Context::new(0, "");
+        ctx.insert(Trace::default());
+        ctx
+    }
+
+    #[test]
+    fn registration_order_without_constraints() {
+        let schedule = Schedule::new()
+            .add_system(Stage::Update, traced("a"))
+            .add_system(Stage::Update, traced("b"))
+            .add_system(Stage::Update, traced("c"));
+        assert_eq!(schedule.order(Stage::Update), Ok(vec!["a", "b", "c"]));
+        assert_eq!(schedule.order(Stage::Startup), Ok(vec![]));
+    }
+
+    #[test]
+    fn before_and_after() {
+        let schedule = Schedule::new()
+            .add_system(Stage::Update, traced("render").after("physics"))
+            .add_system(Stage::Update, traced("physics").after("input"))
+            .add_system(Stage::Update, traced("input"))
+            .add_system(Stage::Update, traced("audio").before("render"));
+
+        assert_eq!(
+            schedule.order(Stage::Update),
+            Ok(vec!["input", "physics", "audio", "render"])
+        );
+    }
+
+    #[test]
+    fn stages_run_in_order() {
+        let schedule = Schedule::new()
+            .add_system(Stage::Shutdown, traced("save"))
+            .add_system(Stage::Update, traced("tick"))
+            .add_system(Stage::Startup, traced("load"));
+
+        let ctx = world();
+        schedule.run(&ctx, 3).unwrap();
+        assert_eq!(
+            *ctx.get::<Trace>().unwrap().borrow(),
+            ["load", "tick", "tick", "tick", "save"]
+        );
+    }
+
+    /// 系统之间通过共享的上下文通信
+    #[test]
+    fn shared_world() {
+        #[derive(Default)]
+        struct Position {
+            x: Cell<i32>,
+        }
+        struct Velocity {
+            dx: i32,
+        }
+
+        let schedule = Schedule::new()
+            .add_system(
+                Stage::Update,
+                System::new("move", |pos: Res<Position>, vel: Res<Velocity>| {
+                    pos.x.set(pos.x.get() + vel.dx)
+                }),
+            )
+            .add_system(
+                Stage::Update,
+                System::new("report", |pos: Res<Position>| pos.x.get()).after("move"),
+            );
+
+        let mut ctx = world();
+        ctx.insert(Position::default());
+        ctx.insert(Velocity { dx: 2 });
+
+        schedule.run(&ctx, 5).unwrap();
+        assert_eq!(ctx.get::<Position>().unwrap().x.get(), 10);
+
+        let results = schedule.run_stage(Stage::Update, &ctx).unwrap();
+        assert_eq!(
+            results,
+            [
+                ("move".to_string(), Response::Empty),
+                ("report".to_string(), Response::Text("12".into())),
+            ]
+        );
+    }
+
+    #[test]
+    fn detect_cycle() {
+        let schedule = Schedule::new()
+            .add_system(Stage::Update, traced("free"))
+            .add_system(Stage::Update, traced("a").before("b"))
+            .add_system(Stage::Update, traced("b").before("c"))
+            .add_system(Stage::Update, traced("c").before("a"))
+            .add_system(Stage::Update, traced("tail").after("c"));
+
+        let err = schedule.order(Stage::Update).unwrap_err();
+        assert_eq!(
+            err,
+            ScheduleError::Cycle(vec!["a".into(), "b".into(), "c".into(), "a".into()])
+        );
+        assert_eq!(err.to_string(), "ordering cycle: a -> b -> c -> a");
+
+        // 有环时一个系统都不会执行
+        let ctx = world();
+        assert!(schedule.run(&ctx, 1).is_err());
+        assert!(ctx.get::<Trace>().unwrap().borrow().is_empty());
+    }
+
+    #[test]
+    fn self_cycle() {
+        let schedule = Schedule::new().add_system(Stage::Startup, traced("a").after("a"));
+        assert_eq!(
+            schedule.order(Stage::Startup),
+            Err(ScheduleError::Cycle(vec!["a".into(), "a".into()]))
+        );
+    }
+
+    #[test]
+    fn invalid_constraints() {
+        // 约束只在同一阶段内生效
+        let schedule = Schedule::new()
+            .add_system(Stage::Startup, traced("load"))
+            .add_system(Stage::Update, traced("tick").after("load"));
+        assert_eq!(
+            schedule.order(Stage::Update),
+            Err(ScheduleError::Unknown {
+                system: "tick".into(),
+                dependency: "load".into(),
+            })
+        );
+
+        let schedule = Schedule::new()
+            .add_system(Stage::Update, traced("tick"))
+            .add_system(Stage::Update, traced("tick"));
+        assert!(matches!(
+            schedule.order(Stage::Update),
+            Err(ScheduleError::Duplicate { .. })
+        ));
+    }
+
+    #[test]
+    fn failed_system() {
+        let schedule = Schedule::new().add_system(
+            Stage::Update,
+            System::new("needs_missing", |_: Res<String>| {}),
+        );
+        let err = schedule.run(&world(), 1).unwrap_err();
+        assert!(
+            matches!(err, ScheduleError::Failed { ref system, .. } if system == "needs_missing")
+        );
+    }
+}
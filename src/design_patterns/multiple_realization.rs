@@ -1,52 +1,116 @@
 //! 多重实现
 //! 泛型系统可为一个类型实现多个不同泛型的特征  
 //! 这里实现多个 AsRef
+//!
+//! 手写的 AsRef 推广为通用的组件机制 见 component
+//! 职业集合、特征对象的向上/向下转型和序列化 见 profession
 
 #![allow(dead_code)]
 
+pub mod component;
+pub mod profession;
+
+use std::io::{self, Write};
+
+use crate::impl_has;
+use component::{Components, Has};
+use profession::{Engineer, Professions, Teacher};
+
 struct Person {
     pub age: u32,
     pub name: String,
 }
 
-trait Profession {
-    fn describe(&self) -> String;
+struct Citizen {
+    person: Person,
+    professions: Professions,
 }
 
-struct Citizen {
+// 原本手写的 AsRef<Person> 等实现 现在由宏生成（同时生成 Has 和 AsMut）
+impl_has!(Citizen {
     person: Person,
-    profession: Box<dyn Profession>,
+    professions: Professions,
+});
+
+/// 只要求持有 Person 组件 不关心具体是哪个类型
+fn person_info(holder: &impl Has<Person>) -> String {
+    let person = holder.component();
+    format!("person: name is {}, age is {}", person.name, person.age)
 }
 
-impl AsRef<Person> for Citizen {
-    fn as_ref(&self) -> &Person {
-        &self.person
-    }
+fn profession_describe(holder: &impl Has<Professions>) -> Vec<String> {
+    holder
+        .component()
+        .iter()
+        .map(|profession| format!("profession: describe is {}", profession.describe()))
+        .collect()
 }
 
-impl AsRef<Box<dyn Profession>> for Citizen {
-    fn as_ref(&self) -> &Box<dyn Profession> {
-        &self.profession
+/// 多重实现的简单演示 同一个对象可以按需借用成不同类型
+pub fn test_all(out: &mut dyn Write) -> io::Result<()> {
+    writeln!(out, "\n\nmultiple_realization:")?;
+    let alice = Citizen {
+        person: Person {
+            age: 56,
+            name: "Alice".into(),
+        },
+        professions: Professions::new()
+            .with(Teacher {
+                subject: "math".into(),
+            })
+            .with(Engineer { level: 3 }),
+    };
+
+    let person: &Person = alice.as_ref();
+    let professions: &Professions = alice.as_ref();
+    writeln!(out, "person: name is {}, age is {}", person.name, person.age)?;
+    for describe in profession_describe(&alice) {
+        writeln!(out, "{}", describe)?;
     }
+    if let Some(engineer) = professions.get::<Engineer>() {
+        writeln!(out, "downcast: engineer level is {}", engineer.level)?;
+    }
+    write!(out, "encoded:\n{}", professions.encode())?;
+
+    let bob = Components::new().with(Person {
+        age: 30,
+        name: "Bob".into(),
+    });
+    if let Some(person) = bob.get::<Person>() {
+        writeln!(out, "from components, {}", person_info(person))?;
+    }
+    Ok(())
 }
 
 #[cfg(test)]
 mod test {
+    use super::profession::Profession;
     use super::*;
 
-    fn print_person_info(person: &Person) {
-        println!("person: name is {}, age is {}", person.name, person.age)
+    fn print_person_info(person: &impl Has<Person>) {
+        println!("{}", person_info(person))
     }
 
-    fn print_profession_describe(p: &Box<dyn Profession>) {
-        println!("profession: describe is {}", p.describe());
+    fn print_profession_describe(p: &impl Has<Professions>) {
+        for describe in profession_describe(p) {
+            println!("{}", describe);
+        }
     }
 
-    struct Teacher();
+    #[derive(Debug)]
+    struct Farmer();
 
-    impl Profession for Teacher {
+    impl Profession for Farmer {
         fn describe(&self) -> String {
-            "this is a teacher".into()
+            "this is a farmer".into()
+        }
+
+        fn name(&self) -> &'static str {
+            "farmer"
+        }
+
+        fn encode(&self) -> String {
+            String::new()
         }
     }
 
@@ -57,11 +121,79 @@ mod test {
                 age: 56,
                 name: "Alice".into(),
             },
-            profession: Box::new(Teacher()),
+            professions: Professions::new().with(Farmer()),
         };
 
         // 演示
-        print_person_info(alice.as_ref());
-        print_profession_describe(alice.as_ref());
+        print_person_info(&alice);
+        print_profession_describe(&alice);
+        // 原来的写法仍然可用
+        let person: &Person = alice.as_ref();
+        print_person_info(person);
+    }
+
+    /// 同一个函数可以作用于任何持有 Person 的类型
+    #[test]
+    fn any_holder() {
+        struct Student {
+            person: Person,
+            grade: u8,
+        }
+        impl_has!(Student {
+            person: Person,
+            grade: u8,
+        });
+
+        let mut tom = Student {
+            person: Person {
+                age: 12,
+                name: "Tom".into(),
+            },
+            grade: 6,
+        };
+        assert_eq!(person_info(&tom), "person: name is Tom, age is 12");
+
+        let person: &mut Person = tom.as_mut();
+        person.age += 1;
+        // Student 同时实现了多个 Has 需要指明类型
+        *Has::<u8>::component_mut(&mut tom) += 1;
+        assert_eq!(person_info(&tom), "person: name is Tom, age is 13");
+        assert_eq!(tom.grade, 7);
+
+        let components = Components::new().with(Person {
+            age: 40,
+            name: "Eve".into(),
+        });
+        assert_eq!(
+            person_info(components.get::<Person>().unwrap()),
+            "person: name is Eve, age is 40"
+        );
+    }
+
+    #[test]
+    fn multiple_professions() {
+        let mut alice = Citizen {
+            person: Person {
+                age: 56,
+                name: "Alice".into(),
+            },
+            professions: Professions::new()
+                .with(Farmer())
+                .with(Engineer { level: 2 }),
+        };
+        assert_eq!(
+            profession_describe(&alice),
+            [
+                "profession: describe is this is a farmer",
+                "profession: describe is this is a level 2 engineer"
+            ]
+        );
+
+        let professions: &mut Professions = alice.as_mut();
+        professions.push(Teacher {
+            subject: "art".into(),
+        });
+        assert!(alice.professions.contains::<Farmer>());
+        assert_eq!(alice.professions.get::<Teacher>().unwrap().subject, "art");
     }
 }
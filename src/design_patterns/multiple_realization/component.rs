@@ -0,0 +1,135 @@
+//! 组件
+//! 把 [`Citizen`](super::Citizen) 手写 AsRef 的做法推广开：
+//! - 静态组件：结构体的字段 用 [`Has`] 特征按类型借用 由 [`impl_has!`] 宏批量生成实现
+//! - 动态组件：[`Components`] 容器 运行时可以挂载任意数量、任意类型的组件
+//!
+//! 使用方只需要声明“需要某种组件” 而不关心组件挂在哪个结构体上
+
+use crate::design_patterns::inversion::extension::Extensions;
+
+/// 持有类型为 T 的组件
+/// 与 AsRef / AsMut 类似 但把可变和不可变访问放在同一个特征里
+pub trait Has<T: ?Sized> {
+    fn component(&self) -> &T;
+    fn component_mut(&mut self) -> &mut T;
+}
+
+/// 任何类型都持有它自己
+impl<T> Has<T> for T {
+    fn component(&self) -> &T {
+        self
+    }
+
+    fn component_mut(&mut self) -> &mut T {
+        self
+    }
+}
+
+/// 为结构体的每个字段生成 Has / AsRef / AsMut 实现（类似于 derive 的效果）
+///
+/// ```ignore
+/// impl_has!(Citizen { person: Person, profession: Box<dyn Profession> });
+/// ```
+///
+/// 注意同一个结构体中的字段类型不能重复 否则实现会冲突
+#[macro_export]
+macro_rules! impl_has {
+    ($holder:ty { $($field:ident: $component:ty),* $(,)? }) => {
+        $(
+            impl $crate::design_patterns::multiple_realization::component::Has<$component> for $holder {
+                fn component(&self) -> &$component {
+                    &self.$field
+                }
+
+                fn component_mut(&mut self) -> &mut $component {
+                    &mut self.$field
+                }
+            }
+
+            impl AsRef<$component> for $holder {
+                fn as_ref(&self) -> &$component {
+                    &self.$field
+                }
+            }
+
+            impl AsMut<$component> for $holder {
+                fn as_mut(&mut self) -> &mut $component {
+                    &mut self.$field
+                }
+            }
+        )*
+    };
+}
+
+/// 动态组件容器 每种类型最多挂载一个组件
+/// 与处理函数上下文的扩展存储是同一种结构 直接复用
+pub type Components = Extensions;
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[derive(Debug, PartialEq)]
+    struct Health(u32);
+    #[derive(Debug, PartialEq)]
+    struct Name(&'static str);
+
+    struct Player {
+        health: Health,
+        name: Name,
+    }
+
+    impl_has!(Player {
+        health: Health,
+        name: Name,
+    });
+
+    fn heal(holder: &mut impl Has<Health>, amount: u32) {
+        holder.component_mut().0 += amount;
+    }
+
+    fn greet(holder: &impl Has<Name>) -> String {
+        format!("hello {}", holder.component().0)
+    }
+
+    #[test]
+    fn static_components() {
+        let mut player = Player {
+            health: Health(10),
+            name: Name("bob"),
+        };
+
+        heal(&mut player, 5);
+        assert_eq!(player.health, Health(15));
+        assert_eq!(greet(&player), "hello bob");
+
+        // 宏同时生成了 AsRef / AsMut
+        let name: &Name = player.as_ref();
+        assert_eq!(name, &Name("bob"));
+        let health: &mut Health = player.as_mut();
+        health.0 = 1;
+        assert_eq!(player.health, Health(1));
+
+        // 组件本身也满足 Has
+        let mut health = Health(0);
+        heal(&mut health, 3);
+        assert_eq!(health, Health(3));
+    }
+
+    #[test]
+    fn dynamic_components() {
+        let mut components = Components::new().with(Health(10)).with(Name("alice"));
+        assert_eq!(components.len(), 2);
+        assert!(components.contains::<Name>());
+
+        heal(components.get_mut::<Health>().unwrap(), 1);
+        assert_eq!(components.get::<Health>(), Some(&Health(11)));
+        assert_eq!(greet(components.get::<Name>().unwrap()), "hello alice");
+
+        assert_eq!(components.insert(Health(1)), Some(Health(11)));
+        assert_eq!(components.remove::<Name>(), Some(Name("alice")));
+        assert_eq!(components.get::<Name>(), None);
+        assert_eq!(components.remove::<Name>(), None);
+        assert!(!components.is_empty());
+    }
+}
@@ -0,0 +1,435 @@
+//! 职业
+//! 一个公民可以同时拥有多个职业 用 Vec<Box<dyn Profession>> 保存
+//! - 向下转型：Profession 以 Any 为父特征 可以从 dyn Profession 取回具体类型
+//! - 向上转型：dyn Profession 可以直接转换为父特征对象 &dyn Any / &dyn Debug（Rust 1.86 起原生支持）
+//! - 序列化：每行一个职业 `名字: 内容` 通过 [`Registry`] 按名字找到反序列化函数
+
+use std::any::Any;
+use std::collections::HashMap;
+use std::error::Error;
+use std::fmt;
+
+/// 职业
+/// 父特征 Any 使得特征对象可以向下转型 Debug 使得集合可以直接打印
+pub trait Profession: Any + fmt::Debug {
+    fn describe(&self) -> String;
+
+    /// 序列化时使用的名字 需要与 [`Decode::NAME`] 一致
+    fn name(&self) -> &'static str;
+
+    /// 序列化的内容 不能包含换行
+    fn encode(&self) -> String;
+}
+
+/// 可以从文本还原的职业
+/// 单独成一个特征：带关联常量和返回 Self 的方法会让 Profession 无法作为特征对象
+pub trait Decode: Profession + Sized {
+    const NAME: &'static str;
+
+    fn decode(payload: &str) -> Result<Self, String>;
+}
+
+/// 为特征对象本身实现方法 用法与 `dyn Any` 相同
+impl dyn Profession {
+    pub fn is<T: Profession>(&self) -> bool {
+        // 向上转型为 &dyn Any 再判断
+        (self as &dyn Any).is::<T>()
+    }
+
+    pub fn downcast_ref<T: Profession>(&self) -> Option<&T> {
+        (self as &dyn Any).downcast_ref()
+    }
+
+    pub fn downcast_mut<T: Profession>(&mut self) -> Option<&mut T> {
+        (self as &mut dyn Any).downcast_mut()
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Teacher {
+    pub subject: String,
+}
+
+impl Profession for Teacher {
+    fn describe(&self) -> String {
+        format!("this is a {} teacher", self.subject)
+    }
+
+    fn name(&self) -> &'static str {
+        Self::NAME
+    }
+
+    /// 解析时会去掉首尾空白 所以空的、首尾有空白的、包含换行或以引号开头的科目
+    /// 用带转义的引号包起来 保证可以原样还原
+    fn encode(&self) -> String {
+        let subject = &self.subject;
+        let plain = !subject.is_empty()
+            && subject.trim() == subject
+            && !subject.starts_with('"')
+            && !subject.contains(['\n', '\r']);
+        if plain {
+            return subject.clone();
+        }
+        let mut quoted = String::from('"');
+        for c in subject.chars() {
+            match c {
+                '"' => quoted.push_str("\\\""),
+                '\\' => quoted.push_str("\\\\"),
+                '\n' => quoted.push_str("\\n"),
+                '\r' => quoted.push_str("\\r"),
+                c => quoted.push(c),
+            }
+        }
+        quoted.push('"');
+        quoted
+    }
+}
+
+impl Decode for Teacher {
+    const NAME: &'static str = "teacher";
+
+    fn decode(payload: &str) -> Result<Self, String> {
+        let Some(quoted) = payload.strip_prefix('"') else {
+            if payload.is_empty() {
+                return Err("subject is empty".into());
+            }
+            return Ok(Teacher {
+                subject: payload.to_string(),
+            });
+        };
+
+        let mut subject = String::new();
+        let mut chars = quoted.chars();
+        loop {
+            match chars.next() {
+                Some('"') => break,
+                Some('\\') => match chars.next() {
+                    Some('"') => subject.push('"'),
+                    Some('\\') => subject.push('\\'),
+                    Some('n') => subject.push('\n'),
+                    Some('r') => subject.push('\r'),
+                    other => return Err(format!("invalid escape {other:?} in {payload:?}")),
+                },
+                Some(c) => subject.push(c),
+                None => return Err(format!("unterminated subject {payload:?}")),
+            }
+        }
+        if !chars.as_str().is_empty() {
+            return Err(format!("unexpected text after subject {payload:?}"));
+        }
+        Ok(Teacher { subject })
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Engineer {
+    pub level: u8,
+}
+
+impl Profession for Engineer {
+    fn describe(&self) -> String {
+        format!("this is a level {} engineer", self.level)
+    }
+
+    fn name(&self) -> &'static str {
+        Self::NAME
+    }
+
+    fn encode(&self) -> String {
+        self.level.to_string()
+    }
+}
+
+impl Decode for Engineer {
+    const NAME: &'static str = "engineer";
+
+    fn decode(payload: &str) -> Result<Self, String> {
+        payload
+            .parse()
+            .map(|level| Engineer { level })
+            .map_err(|err| format!("invalid level {payload:?}: {err}"))
+    }
+}
+
+/// 职业集合 同一种职业可以出现多次
+#[derive(Debug, Default)]
+pub struct Professions {
+    items: Vec<Box<dyn Profession>>,
+}
+
+impl Professions {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn with(mut self, profession: impl Profession) -> Self {
+        self.push(profession);
+        self
+    }
+
+    pub fn push(&mut self, profession: impl Profession) {
+        self.items.push(Box::new(profession));
+    }
+
+    pub fn len(&self) -> usize {
+        self.items.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.items.is_empty()
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = &dyn Profession> {
+        self.items.iter().map(|profession| profession.as_ref())
+    }
+
+    /// 第一个类型为 T 的职业
+    pub fn get<T: Profession>(&self) -> Option<&T> {
+        self.iter().find_map(|profession| profession.downcast_ref())
+    }
+
+    pub fn get_mut<T: Profession>(&mut self) -> Option<&mut T> {
+        self.items
+            .iter_mut()
+            .find_map(|profession| profession.downcast_mut())
+    }
+
+    /// 所有类型为 T 的职业
+    pub fn of_type<T: Profession>(&self) -> impl Iterator<Item = &T> {
+        self.iter()
+            .filter_map(|profession| profession.downcast_ref())
+    }
+
+    pub fn contains<T: Profession>(&self) -> bool {
+        self.iter().any(|profession| profession.is::<T>())
+    }
+
+    /// 移除并返回所有类型为 T 的职业
+    pub fn remove<T: Profession>(&mut self) -> Vec<T> {
+        let (removed, kept) = std::mem::take(&mut self.items)
+            .into_iter()
+            .partition(|profession| profession.is::<T>());
+        self.items = kept;
+        removed
+            .into_iter()
+            // Box<dyn Profession> 向上转型为 Box<dyn Any> 后才能按值取回
+            .filter_map(|profession| (profession as Box<dyn Any>).downcast().ok())
+            .map(|profession: Box<T>| *profession)
+            .collect()
+    }
+
+    /// 序列化为文本 每行一个职业
+    pub fn encode(&self) -> String {
+        self.iter()
+            .map(|profession| format!("{}: {}\n", profession.name(), profession.encode()))
+            .collect()
+    }
+}
+
+/// 反序列化错误 行号从 1 开始
+#[derive(Debug, PartialEq)]
+pub enum DecodeError {
+    MissingSeparator { line: usize },
+    Unknown { line: usize, name: String },
+    Invalid { line: usize, reason: String },
+}
+
+impl fmt::Display for DecodeError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            DecodeError::MissingSeparator { line } => write!(f, "line {line}: missing ':'"),
+            DecodeError::Unknown { line, name } => {
+                write!(f, "line {line}: unknown profession {name:?}")
+            }
+            DecodeError::Invalid { line, reason } => write!(f, "line {line}: {reason}"),
+        }
+    }
+}
+
+impl Error for DecodeError {}
+
+type DecodeFn = fn(&str) -> Result<Box<dyn Profession>, String>;
+
+/// 名字到反序列化函数的注册表
+/// 特征对象无法直接反序列化（不知道具体类型） 所以需要预先登记
+#[derive(Default)]
+pub struct Registry {
+    decoders: HashMap<&'static str, DecodeFn>,
+}
+
+impl Registry {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn register<T: Decode>(mut self) -> Self {
+        // 不捕获变量的闭包可以转换为函数指针
+        let decode: DecodeFn = |payload| Ok(Box::new(T::decode(payload)?));
+        self.decoders.insert(T::NAME, decode);
+        self
+    }
+
+    /// 解析 [`Professions::encode`] 的输出 忽略空行和 `#` 开头的注释
+    pub fn decode(&self, text: &str) -> Result<Professions, DecodeError> {
+        let mut professions = Professions::new();
+        for (i, line) in text.lines().enumerate() {
+            let line_no = i + 1;
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+
+            let (name, payload) = line
+                .split_once(':')
+                .ok_or(DecodeError::MissingSeparator { line: line_no })?;
+            let name = name.trim();
+            let decode = self
+                .decoders
+                .get(name)
+                .ok_or_else(|| DecodeError::Unknown {
+                    line: line_no,
+                    name: name.to_string(),
+                })?;
+            let profession = decode(payload.trim()).map_err(|reason| DecodeError::Invalid {
+                line: line_no,
+                reason,
+            })?;
+            professions.items.push(profession);
+        }
+        Ok(professions)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn sample() -> Professions {
+        Professions::new()
+            .with(Teacher {
+                subject: "math".into(),
+            })
+            .with(Engineer { level: 3 })
+            .with(Teacher {
+                subject: "music".into(),
+            })
+    }
+
+    #[test]
+    fn downcast() {
+        let mut professions = sample();
+        assert_eq!(professions.len(), 3);
+        assert!(professions.contains::<Engineer>());
+
+        assert_eq!(professions.get::<Teacher>().unwrap().subject, "math");
+        let subjects: Vec<_> = professions
+            .of_type::<Teacher>()
+            .map(|teacher| teacher.subject.as_str())
+            .collect();
+        assert_eq!(subjects, ["math", "music"]);
+
+        professions.get_mut::<Engineer>().unwrap().level += 1;
+        assert_eq!(professions.get::<Engineer>(), Some(&Engineer { level: 4 }));
+
+        let profession: &dyn Profession = &Teacher {
+            subject: "art".into(),
+        };
+        assert!(profession.is::<Teacher>());
+        assert!(profession.downcast_ref::<Engineer>().is_none());
+    }
+
+    #[test]
+    fn remove_by_type() {
+        let mut professions = sample();
+        let teachers = professions.remove::<Teacher>();
+        assert_eq!(teachers.len(), 2);
+        assert_eq!(teachers[1].subject, "music");
+        assert_eq!(professions.len(), 1);
+        assert!(!professions.contains::<Teacher>());
+        assert!(professions.remove::<Teacher>().is_empty());
+    }
+
+    #[test]
+    fn upcast() {
+        let engineer: Box<dyn Profession> = Box::new(Engineer { level: 1 });
+        let debug: &dyn fmt::Debug = engineer.as_ref();
+        assert_eq!(format!("{debug:?}"), "Engineer { level: 1 }");
+
+        let any: Box<dyn Any> = engineer;
+        assert_eq!(any.downcast::<Engineer>().unwrap().level, 1);
+    }
+
+    #[test]
+    fn encode_decode_round_trip() {
+        let professions = sample();
+        let text = professions.encode();
+        assert_eq!(text, "teacher: math\nengineer: 3\nteacher: music\n");
+
+        let registry = Registry::new().register::<Teacher>().register::<Engineer>();
+        let decoded = registry.decode(&text).unwrap();
+        assert_eq!(decoded.encode(), text);
+        assert_eq!(format!("{decoded:?}"), format!("{professions:?}"));
+
+        let commented = registry.decode("# comment\n\n  engineer : 7 \n").unwrap();
+        assert_eq!(commented.get::<Engineer>(), Some(&Engineer { level: 7 }));
+    }
+
+    /// 解析会去掉首尾空白 需要转义的科目也要能原样还原
+    #[test]
+    fn teacher_subject_round_trip() {
+        let registry = Registry::new().register::<Teacher>();
+        for subject in [
+            "",
+            "  ",
+            " art ",
+            "\"quoted\"",
+            "a \\ b",
+            "two\nlines",
+            "x\r",
+        ] {
+            let teacher = Teacher {
+                subject: subject.into(),
+            };
+            let text = Professions::new().with(teacher.clone()).encode();
+            assert_eq!(text.lines().count(), 1, "{text:?}");
+            let decoded = registry.decode(&text).unwrap();
+            assert_eq!(decoded.get::<Teacher>(), Some(&teacher), "{text:?}");
+        }
+        assert_eq!(
+            Teacher {
+                subject: "math".into()
+            }
+            .encode(),
+            "math"
+        );
+        assert_eq!(
+            Teacher {
+                subject: " ".into()
+            }
+            .encode(),
+            "\" \""
+        );
+
+        assert!(Teacher::decode("").is_err());
+        assert!(Teacher::decode("\"open").is_err());
+        assert!(Teacher::decode("\"bad \\x\"").is_err());
+        assert!(Teacher::decode("\"a\" b").is_err());
+    }
+
+    #[test]
+    fn decode_errors() {
+        let registry = Registry::new().register::<Engineer>();
+        assert_eq!(
+            registry.decode("engineer: 1\nengineer").unwrap_err(),
+            DecodeError::MissingSeparator { line: 2 }
+        );
+        assert_eq!(
+            registry.decode("teacher: math").unwrap_err().to_string(),
+            "line 1: unknown profession \"teacher\""
+        );
+        assert!(matches!(
+            registry.decode("engineer: high"),
+            Err(DecodeError::Invalid { line: 1, .. })
+        ));
+    }
+}
@@ -0,0 +1,260 @@
+//! 章节注册表
+//! 所有可以直接运行的章节都登记在这里
+//! 命令行（见 [`crate::cli`]）通过它来枚举和运行章节，新增章节时只需要在 [`LESSONS`] 里加一项
+//! （以及运行 `cargo run -- --update-snapshots` 生成输出快照，见 [`crate::snapshot`]）
+//!
+//! 学习要点不手动维护，而是在编译期用 `include_str!` 读入章节源码，从模块注释（`//! 本章学习：`）里解析
+
+use std::fmt;
+use std::io::{self, Write};
+
+use crate::{course, design_patterns, project_structure};
+
+/// 难度
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Difficulty {
+    Beginner,
+    Intermediate,
+    Advanced,
+}
+
+impl fmt::Display for Difficulty {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = match self {
+            Difficulty::Beginner => "入门",
+            Difficulty::Intermediate => "进阶",
+            Difficulty::Advanced => "高级",
+        };
+        // 使用 pad 以支持 `{:<4}` 之类的对齐
+        f.pad(name)
+    }
+}
+
+/// 章节入口 所有输出都写入 out 而不是直接 println! 便于捕获和比对（见 [`crate::snapshot`]）
+pub type Entry = fn(out: &mut dyn Write) -> io::Result<()>;
+
+/// 一个可运行的章节
+pub struct Lesson {
+    /// 章节标识 命令行中使用 如 `run course1`
+    pub id: &'static str,
+    /// 章节标题
+    pub title: &'static str,
+    pub difficulty: Difficulty,
+    /// 建议先学习的章节标识
+    pub prerequisites: &'static [&'static str],
+    /// 章节源码 用于解析学习要点
+    source: &'static str,
+    /// 章节入口
+    pub entry: Entry,
+}
+
+impl Lesson {
+    /// 学习要点 解析自章节的模块注释
+    pub fn topics(&self) -> Vec<&'static str> {
+        parse_topics(self.source)
+    }
+}
+
+/// 从模块注释中解析学习要点
+/// - 有 `本章学习` 标题时 取标题之后的每一行（去掉列表符号 `- `）
+/// - 否则取模块注释的第一段
+fn parse_topics(source: &'static str) -> Vec<&'static str> {
+    let docs: Vec<&str> = source
+        .lines()
+        .map(str::trim)
+        .take_while(|line| line.starts_with("//!"))
+        .map(|line| line.trim_start_matches("//!").trim())
+        .collect();
+
+    let lines = match docs.iter().position(|line| line.starts_with("本章学习")) {
+        Some(pos) => &docs[pos + 1..],
+        None => {
+            let end = docs
+                .iter()
+                .position(|line| line.is_empty())
+                .unwrap_or(docs.len());
+            &docs[..end]
+        }
+    };
+
+    lines
+        .iter()
+        .map(|line| line.trim_start_matches("- ").trim())
+        .filter(|line| !line.is_empty())
+        .collect()
+}
+
+/// 所有章节 按学习顺序排列
+pub static LESSONS: &[Lesson] = &[
+    Lesson {
+        id: "project_structure",
+        title: "项目结构",
+        difficulty: Difficulty::Beginner,
+        prerequisites: &[],
+        source: include_str!("project_structure.rs"),
+        entry: project_structure::inner_module::test,
+    },
+    Lesson {
+        id: "course1",
+        title: "基础语法",
+        difficulty: Difficulty::Beginner,
+        prerequisites: &[],
+        source: include_str!("course/course1.rs"),
+        entry: course::course1::test_all,
+    },
+    Lesson {
+        id: "course2",
+        title: "所有权",
+        difficulty: Difficulty::Beginner,
+        prerequisites: &["course1"],
+        source: include_str!("course/course2.rs"),
+        entry: course::course2::test_all,
+    },
+    Lesson {
+        id: "course3",
+        title: "结构体方法和单元测试",
+        difficulty: Difficulty::Beginner,
+        prerequisites: &["course1"],
+        source: include_str!("course/course3.rs"),
+        entry: course::course3::test_all,
+    },
+    Lesson {
+        id: "course4",
+        title: "生命周期",
+        difficulty: Difficulty::Intermediate,
+        prerequisites: &["course2"],
+        source: include_str!("course/course4.rs"),
+        entry: course::course4::test_all,
+    },
+    Lesson {
+        id: "course5",
+        title: "链表",
+        difficulty: Difficulty::Intermediate,
+        prerequisites: &["course2", "course3"],
+        source: include_str!("course/course5.rs"),
+        entry: course::course5::test_all,
+    },
+    Lesson {
+        id: "course6",
+        title: "异常处理",
+        difficulty: Difficulty::Intermediate,
+        prerequisites: &["course1"],
+        source: include_str!("course/course6.rs"),
+        entry: course::course6::test_all,
+    },
+    Lesson {
+        id: "course7",
+        title: "特征",
+        difficulty: Difficulty::Intermediate,
+        prerequisites: &["course3"],
+        source: include_str!("course/course7.rs"),
+        entry: course::course7::test_all,
+    },
+    Lesson {
+        id: "course8",
+        title: "多线程并发",
+        difficulty: Difficulty::Advanced,
+        prerequisites: &["course2", "course7"],
+        source: include_str!("course/course8.rs"),
+        entry: course::course8::test_all,
+    },
+    Lesson {
+        id: "course9",
+        title: "常用特征",
+        difficulty: Difficulty::Intermediate,
+        prerequisites: &["course6", "course7"],
+        source: include_str!("course/course9.rs"),
+        entry: course::course9::test_all,
+    },
+    Lesson {
+        id: "course10",
+        title: "运算符重载",
+        difficulty: Difficulty::Intermediate,
+        prerequisites: &["course1", "course9"],
+        source: include_str!("course/course10.rs"),
+        entry: course::course10::test_all,
+    },
+    Lesson {
+        id: "inversion",
+        title: "控制反转",
+        difficulty: Difficulty::Advanced,
+        prerequisites: &["course4", "course7", "course8"],
+        source: include_str!("design_patterns/inversion.rs"),
+        entry: design_patterns::inversion::test_all,
+    },
+    Lesson {
+        id: "multiple_realization",
+        title: "多重实现",
+        difficulty: Difficulty::Intermediate,
+        prerequisites: &["course7"],
+        source: include_str!("design_patterns/multiple_realization.rs"),
+        entry: design_patterns::multiple_realization::test_all,
+    },
+];
+
+/// 按标识查找章节
+pub fn find(id: &str) -> Option<&'static Lesson> {
+    LESSONS.iter().find(|lesson| lesson.id == id)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn ids_are_unique() {
+        for (i, lesson) in LESSONS.iter().enumerate() {
+            assert!(
+                LESSONS[i + 1..].iter().all(|other| other.id != lesson.id),
+                "duplicate lesson id {}",
+                lesson.id
+            );
+        }
+    }
+
+    #[test]
+    fn find_lesson() {
+        assert_eq!(find("course1").map(|lesson| lesson.id), Some("course1"));
+        assert!(find("course404").is_none());
+    }
+
+    /// 前置章节必须存在 且排在当前章节之前
+    #[test]
+    fn prerequisites_come_first() {
+        for (i, lesson) in LESSONS.iter().enumerate() {
+            for prerequisite in lesson.prerequisites {
+                let pos = LESSONS.iter().position(|other| other.id == *prerequisite);
+                assert!(
+                    matches!(pos, Some(pos) if pos < i),
+                    "{} requires unknown or later lesson {}",
+                    lesson.id,
+                    prerequisite
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn every_lesson_has_topics() {
+        for lesson in LESSONS {
+            assert!(!lesson.topics().is_empty(), "{} has no topics", lesson.id);
+        }
+    }
+
+    #[test]
+    fn topics_from_header() {
+        let source = "//! 本章学习：\n//! - 格式化和输出\n//! 结构体\n\n//! 不是模块注释";
+        assert_eq!(parse_topics(source), ["格式化和输出", "结构体"]);
+
+        assert_eq!(
+            parse_topics(include_str!("course/course1.rs"))[..2],
+            ["普通注释 文档注释（解析成HTML帮助文档）", "格式化和输出"]
+        );
+    }
+
+    #[test]
+    fn topics_without_header() {
+        let source = "//! 反转  \n//! 控制反转  \n//!\n//! 这里是细节\nfn main() {}";
+        assert_eq!(parse_topics(source), ["反转", "控制反转"]);
+    }
+}
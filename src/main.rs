@@ -2,20 +2,73 @@
 
 #![allow(dead_code)]
 
+mod cli;
 mod course;
 mod design_patterns;
+mod lesson;
 mod project_structure;
+mod runner;
+mod snapshot;
 
 use std::collections::HashMap;
+use std::io;
+use std::process;
 
-use course::course1::test_all as test1;
-use course::course2::test_all as test2;
+use cli::Command;
 
-/// 项目结构 和 基础课程(course)
-fn run_test() {
-    project_structure::inner_module::test();
-    test1();
-    test2();
+/// 执行命令行命令
+fn execute(command: Command) -> Result<(), String> {
+    match command {
+        Command::Debug => {
+            println!("Hello, world!");
+            run_debug();
+        }
+        Command::Help => println!("{}", cli::USAGE),
+        Command::List => {
+            for lesson in lesson::LESSONS {
+                let prerequisites = match lesson.prerequisites {
+                    [] => "-".to_string(),
+                    ids => ids.join(", "),
+                };
+                println!(
+                    "{:<22}[{}] {}（前置: {}）",
+                    lesson.id, lesson.difficulty, lesson.title, prerequisites
+                );
+                for topic in lesson.topics() {
+                    println!("{:<22}  - {}", "", topic);
+                }
+            }
+        }
+        Command::Run(id) => {
+            let lesson = lesson::find(&id)
+                .ok_or_else(|| format!("未找到章节: {id}（使用 list 查看所有章节）"))?;
+            let summary = runner::run_lessons([lesson], &mut io::stdout())
+                .map_err(|err| err.to_string())?;
+            if !summary.all_passed() {
+                return Err(summary.to_string());
+            }
+        }
+        Command::RunAll => {
+            // 某个章节 panic 不影响后续章节 最后统一汇报
+            let summary = runner::run_lessons(lesson::LESSONS, &mut io::stdout())
+                .map_err(|err| err.to_string())?;
+            println!("\n{summary}");
+            if !summary.all_passed() {
+                return Err("部分章节运行失败".to_string());
+            }
+        }
+        Command::Bench => design_patterns::dispatch::bench(),
+        Command::UpdateSnapshots => {
+            let changed = snapshot::update(lesson::LESSONS)?;
+            if changed.is_empty() {
+                println!("所有快照都是最新的");
+            }
+            for id in changed {
+                println!("已更新快照: {}", snapshot::path(id).display());
+            }
+        }
+    }
+    Ok(())
 }
 
 /// VSCode 中调试 rust
@@ -83,10 +136,18 @@ fn run_debug() {
 /// 如何新建一个 rust 项目？使用命令 `cargo new project_name`
 /// 开发时调试，编译并运行（不做优化） `cargo run`
 /// 编译项目 `cargo build --release`
+///
+/// 运行章节 `cargo run -- list` / `cargo run -- run course1` / `cargo run -- run --all`
 fn main() {
-    println!("Hello, world!");
-
-    // run_test();
-
-    run_debug();
+    let command = match Command::parse(std::env::args().skip(1)) {
+        Ok(command) => command,
+        Err(err) => {
+            eprintln!("{err}\n\n{}", cli::USAGE);
+            process::exit(2);
+        }
+    };
+    if let Err(err) = execute(command) {
+        eprintln!("{err}");
+        process::exit(1);
+    }
 }
@@ -3,6 +3,8 @@
 //! 这种方式在新版里取代了旧方式（文件夹下创建 mod.rs）
 
 pub mod inner_module {
+    use std::io::{self, Write};
+
     /// 包Package: 及一个工程 必须由一个 Cargo.toml 文件来管理
     ///
     ///
@@ -23,7 +25,7 @@ pub mod inner_module {
     /// - 文件 一个rs文件默认为一个模块
     /// - 文件夹 与文件夹同级创建同名rs文件（旧方式：使用mod.rs）
     /// - mod关键字定义 如这里的inner_module 可以多层嵌套
-    pub fn test() {
-        println!("project_structure")
+    pub fn test(out: &mut dyn Write) -> io::Result<()> {
+        writeln!(out, "project_structure")
     }
 }
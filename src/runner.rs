@@ -0,0 +1,296 @@
+//! 章节运行器
+//! 每个章节都在 `catch_unwind` 中运行 一个章节 panic 不会影响后续章节
+//!
+//! 默认的 panic hook 会把信息直接打印到 stderr 并且拿不到结构化的信息
+//! 这里安装一个自定义 hook：在运行章节的线程上把 panic 信息和位置记录下来 其它情况仍然交给原来的 hook
+//!
+//! 章节的输出写入可注入的 Write 运行器先把每个章节的输出写入内存缓冲
+//! 章节结束后（包括 panic 的情况）再整体转发 这样每个章节的输出可以单独检查（见 snapshot）
+
+use std::cell::RefCell;
+use std::fmt;
+use std::io::{self, Write};
+use std::panic::{self, AssertUnwindSafe, PanicHookInfo};
+use std::sync::Once;
+
+use crate::lesson::{Entry, Lesson};
+
+/// 一次 panic 的信息
+#[derive(Debug, Clone, PartialEq)]
+pub struct PanicReport {
+    pub message: String,
+    /// `文件:行:列`
+    pub location: Option<String>,
+}
+
+impl fmt::Display for PanicReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match &self.location {
+            Some(location) => write!(f, "panicked at {location}: {}", self.message),
+            None => write!(f, "panicked: {}", self.message),
+        }
+    }
+}
+
+/// 章节失败的原因
+#[derive(Debug)]
+pub enum Failure {
+    Panicked(PanicReport),
+    /// 章节入口返回的 io 错误
+    Io(io::Error),
+}
+
+impl fmt::Display for Failure {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Failure::Panicked(report) => write!(f, "{report}"),
+            Failure::Io(err) => write!(f, "io error: {err}"),
+        }
+    }
+}
+
+/// 单个章节的运行结果
+#[derive(Debug)]
+pub struct LessonResult {
+    pub id: &'static str,
+    /// 章节的完整输出（panic 时为 panic 之前的输出）
+    pub output: String,
+    pub failure: Option<Failure>,
+}
+
+impl LessonResult {
+    pub fn passed(&self) -> bool {
+        self.failure.is_none()
+    }
+}
+
+thread_local! {
+    /// None 表示当前线程没有在运行章节 panic 交给原来的 hook 处理
+    static CAPTURED: RefCell<Option<Option<PanicReport>>> = const { RefCell::new(None) };
+}
+
+/// hook 是进程级的 只安装一次 并通过线程局部变量区分是否需要捕获
+/// 这样并行运行的单元测试（以及章节里自己创建的线程）不受影响
+fn install_hook() {
+    static INSTALL: Once = Once::new();
+    INSTALL.call_once(|| {
+        let previous = panic::take_hook();
+        panic::set_hook(Box::new(move |info| {
+            let captured = CAPTURED.with(|captured| match &mut *captured.borrow_mut() {
+                Some(slot) => {
+                    *slot = Some(report(info));
+                    true
+                }
+                None => false,
+            });
+            if !captured {
+                previous(info);
+            }
+        }));
+    });
+}
+
+fn report(info: &PanicHookInfo<'_>) -> PanicReport {
+    // panic! 的参数是字面量时 payload 为 &str 带格式化参数时为 String
+    let payload = info.payload();
+    let message = if let Some(message) = payload.downcast_ref::<&str>() {
+        message.to_string()
+    } else if let Some(message) = payload.downcast_ref::<String>() {
+        message.clone()
+    } else {
+        "<non-string panic payload>".to_string()
+    };
+    PanicReport {
+        message,
+        location: info.location().map(|location| {
+            format!(
+                "{}:{}:{}",
+                location.file(),
+                location.line(),
+                location.column()
+            )
+        }),
+    }
+}
+
+/// 在隔离环境中运行一个章节 并捕获它的输出
+pub fn run_lesson(id: &'static str, entry: Entry) -> LessonResult {
+    install_hook();
+    CAPTURED.with(|captured| *captured.borrow_mut() = Some(None));
+    let mut buffer = Vec::new();
+    // panic 后不会再使用 buffer 里可能不完整的状态 只是把已经写入的内容取出来 所以可以断言 UnwindSafe
+    let result = panic::catch_unwind(AssertUnwindSafe(|| entry(&mut buffer)));
+    let report = CAPTURED.with(|captured| captured.borrow_mut().take().flatten());
+
+    let failure = match result {
+        Ok(Ok(())) => None,
+        Ok(Err(err)) => Some(Failure::Io(err)),
+        // catch_unwind 返回 Err 时 hook 一定已经记录了信息 这里的兜底只是以防万一
+        Err(_) => Some(Failure::Panicked(report.unwrap_or_else(|| PanicReport {
+            message: "<unknown>".to_string(),
+            location: None,
+        }))),
+    };
+    LessonResult {
+        id,
+        output: String::from_utf8_lossy(&buffer).into_owned(),
+        failure,
+    }
+}
+
+/// 汇总报告
+#[derive(Debug, Default)]
+pub struct Summary {
+    pub results: Vec<LessonResult>,
+}
+
+impl Summary {
+    pub fn passed(&self) -> usize {
+        self.results.iter().filter(|result| result.passed()).count()
+    }
+
+    pub fn failed(&self) -> impl Iterator<Item = &LessonResult> {
+        self.results.iter().filter(|result| !result.passed())
+    }
+
+    pub fn all_passed(&self) -> bool {
+        self.failed().next().is_none()
+    }
+}
+
+impl fmt::Display for Summary {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "运行结果: {} 个章节通过, {} 个章节失败",
+            self.passed(),
+            self.results.len() - self.passed()
+        )?;
+        for result in self.failed() {
+            if let Some(failure) = &result.failure {
+                write!(f, "\n  {}: {}", result.id, failure)?;
+            }
+        }
+        Ok(())
+    }
+}
+
+/// 依次运行所有章节 每个章节结束后把输出转发到 out
+/// 失败信息只记录在返回的 [`Summary`] 中 由调用方决定如何报告
+pub fn run_lessons<'a>(
+    lessons: impl IntoIterator<Item = &'a Lesson>,
+    out: &mut dyn Write,
+) -> io::Result<Summary> {
+    let mut summary = Summary::default();
+    for lesson in lessons {
+        let result = run_lesson(lesson.id, lesson.entry);
+        out.write_all(result.output.as_bytes())?;
+        summary.results.push(result);
+    }
+    Ok(summary)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn fine(out: &mut dyn Write) -> io::Result<()> {
+        writeln!(out, "fine")
+    }
+
+    fn literal(out: &mut dyn Write) -> io::Result<()> {
+        writeln!(out, "before panic")?;
+        panic!("boom");
+    }
+
+    fn formatted(_: &mut dyn Write) -> io::Result<()> {
+        let value = 42;
+        panic!("bad value {value}");
+    }
+
+    fn custom_payload(_: &mut dyn Write) -> io::Result<()> {
+        panic::panic_any(7_u8);
+    }
+
+    fn io_error(_: &mut dyn Write) -> io::Result<()> {
+        Err(io::Error::other("sink closed"))
+    }
+
+    fn panic_report(result: LessonResult) -> PanicReport {
+        match result.failure {
+            Some(Failure::Panicked(report)) => report,
+            other => panic!("expected panic, got {other:?}"),
+        }
+    }
+
+    #[test]
+    fn captures_message_and_location() {
+        let result = run_lesson("fine", fine);
+        assert!(result.passed());
+        assert_eq!(result.output, "fine\n");
+
+        let result = run_lesson("literal", literal);
+        assert_eq!(result.output, "before panic\n");
+        let panic = panic_report(result);
+        assert_eq!(panic.message, "boom");
+        let location = panic.location.unwrap();
+        assert!(location.starts_with("src/runner.rs:"), "{location}");
+
+        let panic = panic_report(run_lesson("formatted", formatted));
+        assert_eq!(panic.message, "bad value 42");
+        assert!(panic.to_string().starts_with("panicked at src/runner.rs:"));
+
+        let panic = panic_report(run_lesson("custom", custom_payload));
+        assert_eq!(panic.message, "<non-string panic payload>");
+
+        let result = run_lesson("io", io_error);
+        assert!(matches!(result.failure, Some(Failure::Io(_))));
+    }
+
+    /// 运行结束后不再捕获 当前线程恢复默认行为
+    #[test]
+    fn capture_is_scoped() {
+        run_lesson("literal", literal);
+        CAPTURED.with(|captured| assert!(captured.borrow().is_none()));
+        assert!(panic::catch_unwind(|| 1).is_ok());
+    }
+
+    #[test]
+    fn summary_report() {
+        let summary = Summary {
+            results: vec![
+                run_lesson("a", fine),
+                run_lesson("b", literal),
+                run_lesson("c", fine),
+                run_lesson("d", formatted),
+            ],
+        };
+        assert_eq!(summary.passed(), 2);
+        assert!(!summary.all_passed());
+
+        let text = summary.to_string();
+        let lines: Vec<&str> = text.lines().collect();
+        assert_eq!(lines[0], "运行结果: 2 个章节通过, 2 个章节失败");
+        assert!(lines[1].starts_with("  b: panicked at src/runner.rs:"));
+        assert!(lines[1].ends_with(": boom"));
+        assert!(lines[2].ends_with(": bad value 42"));
+        assert_eq!(lines.len(), 3);
+    }
+
+    /// 所有注册的章节在运行器中都能通过 新注册的章节在这里 panic 也会被发现
+    #[test]
+    fn registered_lessons_pass() {
+        let mut out = Vec::new();
+        let summary = run_lessons(crate::lesson::LESSONS, &mut out).unwrap();
+        assert_eq!(summary.results.len(), crate::lesson::LESSONS.len());
+        assert!(summary.all_passed(), "{summary}");
+        // 输出按章节顺序转发
+        let out = String::from_utf8(out).unwrap();
+        let expected: String = summary
+            .results
+            .iter()
+            .map(|result| result.output.as_str())
+            .collect();
+        assert_eq!(out, expected);
+    }
+}
@@ -0,0 +1,154 @@
+//! 输出快照
+//! 每个章节的输出保存在 `snapshots/<章节>.txt` 单元测试逐个比对 防止章节的行为被无意改变
+//!
+//! 章节输出有意修改后 运行 `cargo run -- --update-snapshots` 重新生成 再通过 git diff 检查变化
+//!
+//! 内存地址每次运行都不同 比对前把 `0x` 开头的十六进制数统一替换为 `0x[addr]`
+
+use std::fs;
+use std::path::PathBuf;
+
+use crate::lesson::Lesson;
+use crate::runner;
+
+/// 快照目录 编译期确定 与运行时的工作目录无关
+const DIR: &str = concat!(env!("CARGO_MANIFEST_DIR"), "/snapshots");
+
+pub fn path(id: &str) -> PathBuf {
+    PathBuf::from(DIR).join(format!("{id}.txt"))
+}
+
+/// 把 `0x1A2B` `0x7ffd...` 之类的地址替换为 `0x[addr]`
+pub fn normalize(output: &str) -> String {
+    let mut normalized = String::with_capacity(output.len());
+    let mut rest = output;
+    while let Some(pos) = rest.find("0x") {
+        let (before, after) = rest.split_at(pos);
+        normalized.push_str(before);
+        let digits = after[2..]
+            .find(|c: char| !c.is_ascii_hexdigit())
+            .unwrap_or(after.len() - 2);
+        if digits == 0 {
+            normalized.push_str("0x");
+        } else {
+            normalized.push_str("0x[addr]");
+        }
+        rest = &after[2 + digits..];
+    }
+    normalized.push_str(rest);
+    normalized
+}
+
+/// 运行章节并返回规范化后的输出
+pub fn render(lesson: &Lesson) -> Result<String, String> {
+    let result = runner::run_lesson(lesson.id, lesson.entry);
+    match result.failure {
+        Some(failure) => Err(format!("{}: {}", lesson.id, failure)),
+        None => Ok(normalize(&result.output)),
+    }
+}
+
+/// 比较快照 不一致时指出第一处不同的行
+pub fn compare(id: &str, expected: &str, actual: &str) -> Result<(), String> {
+    if expected == actual {
+        return Ok(());
+    }
+    let mut expected_lines = expected.lines();
+    let mut actual_lines = actual.lines();
+    for line in 1.. {
+        match (expected_lines.next(), actual_lines.next()) {
+            (Some(e), Some(a)) if e == a => continue,
+            (None, None) => break,
+            (e, a) => {
+                return Err(format!(
+                    "{id} 第 {line} 行与快照不一致\n  快照: {}\n  实际: {}",
+                    e.unwrap_or("<无>"),
+                    a.unwrap_or("<无>")
+                ))
+            }
+        }
+    }
+    // 各行都相同 只有结尾的换行不同
+    Err(format!("{id} 结尾的换行与快照不一致"))
+}
+
+/// 与保存的快照比对
+pub fn check(lesson: &Lesson) -> Result<(), String> {
+    let actual = render(lesson)?;
+    let expected = fs::read_to_string(path(lesson.id)).map_err(|err| {
+        format!(
+            "无法读取 {} 的快照: {err}（使用 --update-snapshots 生成）",
+            lesson.id
+        )
+    })?;
+    compare(lesson.id, &expected, &actual)
+}
+
+/// 重新生成快照 返回内容有变化的章节
+pub fn update<'a>(
+    lessons: impl IntoIterator<Item = &'a Lesson>,
+) -> Result<Vec<&'static str>, String> {
+    fs::create_dir_all(DIR).map_err(|err| format!("无法创建快照目录 {DIR}: {err}"))?;
+    let mut changed = Vec::new();
+    for lesson in lessons {
+        let output = render(lesson)?;
+        let path = path(lesson.id);
+        if fs::read_to_string(&path).ok().as_deref() == Some(output.as_str()) {
+            continue;
+        }
+        fs::write(&path, output)
+            .map_err(|err| format!("无法写入快照 {}: {err}", path.display()))?;
+        changed.push(lesson.id);
+    }
+    Ok(changed)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::lesson::LESSONS;
+
+    #[test]
+    fn normalize_addresses() {
+        assert_eq!(normalize("addr: 0x7FFD1A2B"), "addr: 0x[addr]");
+        assert_eq!(
+            normalize("a 0x1f, b 0xabc!\nc 0x"),
+            "a 0x[addr], b 0x[addr]!\nc 0x"
+        );
+        assert_eq!(normalize("0xg 10x1"), "0xg 10x[addr]");
+        assert_eq!(normalize("no address"), "no address");
+    }
+
+    #[test]
+    fn compare_reports_first_difference() {
+        assert!(compare("a", "x\ny\n", "x\ny\n").is_ok());
+
+        let err = compare("a", "x\ny\n", "x\nz\n").unwrap_err();
+        assert!(err.starts_with("a 第 2 行与快照不一致"), "{err}");
+        assert!(err.contains("快照: y") && err.contains("实际: z"));
+
+        let err = compare("a", "x\n", "x\ny\n").unwrap_err();
+        assert!(err.contains("快照: <无>"));
+
+        let err = compare("a", "x\n", "x").unwrap_err();
+        assert!(err.contains("换行"));
+    }
+
+    /// 章节输出有意修改时 运行 `cargo run -- --update-snapshots` 更新快照
+    #[test]
+    fn lessons_match_snapshots() {
+        for lesson in LESSONS {
+            if let Err(err) = check(lesson) {
+                panic!("{err}");
+            }
+        }
+    }
+
+    /// 地址被规范化后 多次运行的输出完全一致
+    #[test]
+    fn render_is_stable() {
+        for lesson in LESSONS {
+            assert_eq!(render(lesson), render(lesson), "{}", lesson.id);
+        }
+    }
+}